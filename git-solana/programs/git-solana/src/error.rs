@@ -0,0 +1,161 @@
+use anchor_lang::prelude::*;
+
+use crate::PushDenial;
+
+#[error_code]
+pub enum GitError {
+    #[msg("You are not authorized to perform this action.")]
+    Unauthorized,
+    #[msg("The storage identifier is not valid for its storage kind.")]
+    InvalidStorageId,
+    #[msg("The branch does not exist.")]
+    BranchNotFound,
+    #[msg("The repository has reached its pinned branch limit.")]
+    TooManyPinnedBranches,
+    #[msg("A repository with this name already exists with different casing.")]
+    NameTaken,
+    #[msg("The collaborator import buffer is full.")]
+    ImportBufferFull,
+    #[msg("The requested export chunk does not exist.")]
+    ExportChunkOutOfRange,
+    #[msg("A branch cannot be merged into itself.")]
+    InvalidMerge,
+    #[msg("A branch with this name already exists.")]
+    BranchExists,
+    #[msg("The symbolic ref would form a cycle.")]
+    SymbolicRefCycle,
+    #[msg("Symbolic refs cannot be pointed at a commit directly.")]
+    SymbolicRefNotWritable,
+    #[msg("The commit hash does not match the repository's object format.")]
+    InvalidCommitHash,
+    #[msg("The code owners mapping is invalid.")]
+    InvalidCodeOwners,
+    #[msg("A changed path is missing approval from its code owners.")]
+    MissingCodeOwnerApproval,
+    #[msg("The repository has reached its branch limit.")]
+    TooManyBranches,
+    #[msg("The branch limit must be between 1 and MAX_BRANCHES.")]
+    InvalidBranchLimit,
+    #[msg("A content hash is required when a storage ID is provided.")]
+    MissingContentHash,
+    #[msg("The repository name is invalid.")]
+    InvalidRepoName,
+    #[msg("A repository cannot redirect to itself.")]
+    InvalidRedirect,
+    #[msg("The repository has moved; see its redirect.")]
+    RepoRedirected,
+    #[msg("The event level must be between 0 and 2.")]
+    InvalidEventLevel,
+    #[msg("The branch name is not a valid ref name.")]
+    InvalidBranchName,
+    #[msg("The update does not descend from the current branch tip.")]
+    NonFastForward,
+    #[msg("Too many ancestor hashes were supplied.")]
+    LineageTooLong,
+    #[msg("This report has already been resolved.")]
+    ReportAlreadyResolved,
+    #[msg("A config value is out of range.")]
+    InvalidConfig,
+    #[msg("The repository account holds data outside the program's limits.")]
+    CorruptState,
+    #[msg("The requested initial account space is too small.")]
+    InvalidRepoSpace,
+    #[msg("The repository is still being imported.")]
+    RepoPending,
+    #[msg("The repository is not being imported.")]
+    RepoNotPending,
+    #[msg("This action must be scheduled with request_destructive_action.")]
+    TimelockRequired,
+    #[msg("The scheduled action's timelock has not passed yet.")]
+    TimelockActive,
+    #[msg("The timelock delay is out of range.")]
+    InvalidTimelock,
+    #[msg("A reset needs a non-empty reason within the length limit.")]
+    InvalidResetReason,
+    #[msg("The threshold must be positive.")]
+    InvalidThreshold,
+    #[msg("The key is not a collaborator on this repository.")]
+    CollaboratorNotFound,
+    #[msg("A collaborator note must be non-empty and within the length limit.")]
+    InvalidCollaboratorNote,
+    #[msg("The storage gateway must be an https URL within the length limit.")]
+    InvalidStorageConfig,
+    #[msg("The branch prefix must not be empty.")]
+    EmptyBranchPrefix,
+    #[msg("The commit is neither the branch tip nor one of its recent tips.")]
+    CommitNotRecentTip,
+    #[msg("The token account does not belong to the signer or the gate mint.")]
+    InvalidStakeAccount,
+    #[msg("The signer holds less of the gate mint than the repository requires.")]
+    InsufficientStake,
+    #[msg("The proof was made for a different commit convention.")]
+    ConventionMismatch,
+    #[msg("A branch cannot be swapped with itself.")]
+    InvalidSwap,
+    #[msg("A repository cannot mirror itself.")]
+    InvalidMirror,
+    #[msg("The repository has reached its mirror limit.")]
+    TooManyMirrors,
+    #[msg("The requested branch page does not exist.")]
+    BranchPageOutOfRange,
+    #[msg("A commit status needs a non-empty context and fields within the length limits.")]
+    InvalidCommitStatus,
+    #[msg("The repository has reached its dependency limit.")]
+    TooManyDependencies,
+    #[msg("The repository has reached its collaborator limit.")]
+    TooManyCollaborators,
+    #[msg("A read grant must expire in the future.")]
+    InvalidGrantExpiry,
+    #[msg("A sponsorship needs a positive amount and period.")]
+    InvalidSponsorship,
+    #[msg("The current sponsorship period has not elapsed yet.")]
+    SponsorshipNotDue,
+    #[msg("The sponsorship escrow cannot cover this period.")]
+    SponsorshipUnderfunded,
+    #[msg("Only commits stored on Arweave can be confirmed.")]
+    NotArweaveStorage,
+    #[msg("An invite expiry must lie in the future.")]
+    InvalidInvite,
+    #[msg("The invite has no uses left.")]
+    InviteExhausted,
+    #[msg("The invite has expired.")]
+    InviteExpired,
+    #[msg("A clone report must cover between 1 and MAX_CLONES_PER_REPORT clones.")]
+    InvalidCloneReport,
+    #[msg("Clones were reported too recently.")]
+    CloneReportTooSoon,
+    #[msg("Required checks must be non-empty contexts within the limits.")]
+    InvalidRequiredChecks,
+    #[msg("A required status check has not succeeded for this commit.")]
+    RequiredCheckFailed,
+    #[msg("This action needs a recent prove_owner_control proof.")]
+    OwnerProofStale,
+    #[msg("The repository was archived for inactivity; the owner must call touch_repo.")]
+    RepoAutoArchived,
+    #[msg("The key is already an owner, or is not a co-owner.")]
+    InvalidCoOwner,
+    #[msg("The repository has reached its co-owner limit.")]
+    TooManyCoOwners,
+    #[msg("Name records must be passed exactly when the repository or the owner's name policy needs them.")]
+    InvalidNameRecord,
+    #[msg("The key is already a collaborator.")]
+    DuplicateCollaborator,
+    #[msg("Required checks need a status key to report them.")]
+    StatusKeyNotSet,
+    #[msg("The symbolic ref would chain deeper than the maximum depth.")]
+    SymbolicRefTooDeep,
+}
+
+impl From<PushDenial> for Error {
+    fn from(denial: PushDenial) -> Self {
+        match denial {
+            PushDenial::NotCollaborator => GitError::Unauthorized,
+            PushDenial::RepoRedirected => GitError::RepoRedirected,
+            PushDenial::RepoPending => GitError::RepoPending,
+            PushDenial::BranchNotFound => GitError::BranchNotFound,
+            PushDenial::SymbolicRef => GitError::SymbolicRefNotWritable,
+            PushDenial::RepoArchived => GitError::RepoAutoArchived,
+        }
+        .into()
+    }
+}
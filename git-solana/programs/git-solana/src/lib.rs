@@ -1,140 +1,4610 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::{hash, hashv, Hasher};
+use anchor_lang::system_program;
+use anchor_spl::token::TokenAccount;
+use unicode_normalization::is_nfc;
+
+pub mod error;
+
+pub use error::GitError;
 
 declare_id!("5TQo5Bf6yXp9uywEFbp9YKUyveD2pe2LVXRjY2aWRup5");
 
+/// Maximum number of branches a repository can pin for display ordering.
+pub const MAX_PINNED_BRANCHES: usize = 8;
+
+/// Maximum number of collaborators a single import session can stage.
+pub const MAX_IMPORT_COLLABORATORS: usize = 256;
+
+/// Hard ceiling on the number of entries in a repository's collaborator
+/// list, the owner's included.
+pub const MAX_COLLABORATORS: usize = 512;
+
+/// Most co-owners a repository may have besides its owner.
+pub const MAX_CO_OWNERS: usize = 4;
+
+/// Most signers tracked at once for the emission budget.
+pub const MAX_EMISSION_COUNTERS: usize = MAX_COLLABORATORS + MAX_CO_OWNERS;
+
+/// Lower bound, in lamports, of the refundable repo creation deposit.
+pub const MIN_CREATION_DEPOSIT: u64 = 10_000_000;
+
+/// Upper bound, in lamports, of the refundable repo creation deposit.
+pub const MAX_CREATION_DEPOSIT: u64 = 1_000_000_000;
+
+/// Hard ceiling on the number of branches (including symbolic refs) in a
+/// repository.
+pub const MAX_BRANCHES: u16 = 256;
+
+/// Default per-repo branch limit, roughly what fit in the original fixed
+/// 9000-byte repository allocation.
+pub const DEFAULT_MAX_BRANCHES: u16 = 64;
+
+/// Maximum number of rules in a repository's code owners mapping.
+pub const MAX_CODE_OWNER_RULES: usize = 16;
+
+/// Maximum number of owners per code owners rule.
+pub const MAX_OWNERS_PER_RULE: usize = 4;
+
+/// Maximum length, in bytes, of a code owners path prefix.
+pub const MAX_PATH_PREFIX_LEN: usize = 64;
+
+/// `event_level` at which no events are emitted.
+pub const EVENT_LEVEL_NONE: u8 = 0;
+
+/// `event_level` at which only branch update events are emitted.
+pub const EVENT_LEVEL_BRANCHES: u8 = 1;
+
+/// `event_level` at which every event is emitted.
+pub const EVENT_LEVEL_ALL: u8 = 2;
+
+/// Maximum length, in bytes, of a repository name, bounded by the PDA seed
+/// length limit.
+pub const MAX_REPO_NAME_LEN: usize = 32;
+
+/// Maximum length, in bytes, of a branch name.
+pub const MAX_BRANCH_NAME_LEN: usize = 64;
+
+/// Maximum number of ancestor hashes a client may supply with a push.
+pub const MAX_LINEAGE_LEN: usize = 32;
+
+/// Name of the symbolic ref whose target is the repository's default branch.
+pub const HEAD_REF: &str = "HEAD";
+
+/// Maximum number of hops followed when resolving a symbolic ref.
+pub const MAX_SYMBOLIC_REF_DEPTH: usize = 8;
+
+/// Smallest `Config::default_repo_space`, and its value when the config is
+/// initialized. The account is resized to fit its contents before
+/// `create_repo` returns.
+pub const REPO_INITIAL_SPACE: usize = 256;
+
+/// Largest `Config::default_repo_space`; accounts created through a CPI
+/// cannot start out bigger than this.
+pub const MAX_REPO_INITIAL_SPACE: usize = 10_240;
+
+/// Scheme prefix of the canonical clone reference, `solar://<owner>/<name>`.
+pub const CLONE_REF_SCHEME: &str = "solar://";
+
+/// Layout version of `RepoExport`. Bump whenever the export format changes.
+pub const REPO_EXPORT_VERSION: u8 = 2;
+
+/// Payload bytes per `export_repo` chunk, leaving room for the chunk header
+/// within the 1024-byte return data limit.
+pub const EXPORT_CHUNK_SIZE: usize = 1000;
+
+/// Branches per `list_branches_data` page. Four maximal `ListedBranch`
+/// entries fit within the 1024-byte return data limit.
+pub const BRANCH_PAGE_SIZE: usize = 4;
+
+/// Longest delay, in seconds, an owner may put in front of destructive
+/// actions.
+pub const MAX_DESTRUCTIVE_ACTION_DELAY: u32 = 30 * 24 * 60 * 60;
+
+/// Length, in slots, of the window `RepoSettings::emission_budget` applies
+/// to; roughly one minute.
+pub const EMISSION_WINDOW_SLOTS: u64 = 150;
+
+/// Slots a `prove_owner_control` proof stays valid for destructive
+/// instructions; roughly one minute.
+pub const OWNER_PROOF_MAX_AGE_SLOTS: u64 = 150;
+
+/// Maximum number of storage transactions a single commit may span.
+pub const MAX_BUNDLE_TXS: usize = 8;
+
+/// Maximum length of the reason recorded by `reset_branch`.
+pub const MAX_RESET_REASON_LEN: usize = 128;
+
+/// Maximum length of an owner's note on a collaborator.
+pub const MAX_COLLABORATOR_NOTE_LEN: usize = 64;
+
+/// Maximum length of a repository's storage gateway hint.
+pub const MAX_GATEWAY_LEN: usize = 128;
+
+/// Number of previous tips a branch remembers for `set_verified_commit`.
+pub const MAX_RECENT_TIPS: usize = 4;
+
+/// Maximum number of mirrors a repository can list.
+pub const MAX_MIRRORS: usize = 8;
+
+/// Maximum number of dependencies a repository can declare.
+pub const MAX_DEPENDENCIES: usize = 16;
+
+/// Most clones a single `record_clone` call may report.
+pub const MAX_CLONES_PER_REPORT: u32 = 1_000;
+
+/// Minimum seconds between two `record_clone` calls on a repository.
+pub const CLONE_REPORT_INTERVAL: i64 = 60;
+
+/// Number of entries a user's activity feed keeps before dropping the
+/// oldest.
+pub const MAX_ACTIVITY_ENTRIES: usize = 32;
+
+/// Maximum length of a commit status context such as `build`, bounded by
+/// the PDA seed length limit.
+pub const MAX_STATUS_CONTEXT_LEN: usize = 32;
+
+/// Maximum length of a commit status description.
+pub const MAX_STATUS_DESCRIPTION_LEN: usize = 128;
+
+/// Maximum number of status contexts a branch can require.
+pub const MAX_REQUIRED_CHECKS: usize = 4;
+
+/// Length of the longest commit hash, a SHA-256 hex digest.
+pub const MAX_COMMIT_HASH_LEN: usize = 64;
+
 #[program]
 pub mod git_solana {
     use super::*;
 
-    pub fn create_repo(ctx: Context<CreateRepo>, name: String) -> Result<()> {
+    /// Creates a repository. `deposit_amount` is clamped to
+    /// `config.creation_deposit..=MAX_CREATION_DEPOSIT` and held in the repo
+    /// account until `close_repo` refunds it. `object_format` defaults to
+    /// SHA-1 and `git_config` to git's own defaults. `name_record` claims
+    /// the lowercased name; it is required once the signer has opted in
+    /// with `set_name_policy` and optional otherwise.
+    pub fn create_repo(
+        ctx: Context<CreateRepo>,
+        name: String,
+        deposit_amount: u64,
+        object_format: Option<ObjectFormat>,
+        git_config: Option<GitConfig>,
+    ) -> Result<()> {
+        let deposit =
+            deposit_amount.clamp(ctx.accounts.config.creation_deposit, MAX_CREATION_DEPOSIT);
+        init_repo(
+            &mut ctx.accounts.repo,
+            ctx.accounts.name_record.as_mut(),
+            case_insensitive_names(ctx.program_id, &ctx.accounts.name_policy),
+            ctx.accounts.signer.key(),
+            name,
+            deposit,
+            object_format,
+            git_config,
+        )?;
+        ctx.accounts.repo.bump = ctx.bumps.repo;
+        record_activity(
+            ctx.accounts.activity.as_mut(),
+            ActivityKind::RepoCreated,
+            ctx.accounts.repo.key(),
+        )?;
+        // Sizing the account also escrows the deposit from the signer.
+        let repo = &ctx.accounts.repo;
+        let space = repo.required_space()?;
+        realloc_repo(
+            repo,
+            &ctx.accounts.signer.to_account_info(),
+            &ctx.accounts.system_program,
+            space,
+        )
+    }
+
+    /// Starts a two-phase creation for repositories too large to create in
+    /// one transaction. The account starts out `space` bytes large and stays
+    /// pending, rejecting pushes, until `finalize_repo`. Deposit,
+    /// `object_format` and `git_config` behave as in `create_repo`.
+    pub fn begin_repo(
+        ctx: Context<BeginRepo>,
+        name: String,
+        space: u32,
+        deposit_amount: u64,
+        object_format: Option<ObjectFormat>,
+        git_config: Option<GitConfig>,
+    ) -> Result<()> {
+        require!(
+            space as usize >= REPO_INITIAL_SPACE,
+            GitError::InvalidRepoSpace
+        );
+        let deposit =
+            deposit_amount.clamp(ctx.accounts.config.creation_deposit, MAX_CREATION_DEPOSIT);
+        init_repo(
+            &mut ctx.accounts.repo,
+            ctx.accounts.name_record.as_mut(),
+            case_insensitive_names(ctx.program_id, &ctx.accounts.name_policy),
+            ctx.accounts.signer.key(),
+            name,
+            deposit,
+            object_format,
+            git_config,
+        )?;
+        let repo = &mut ctx.accounts.repo;
+        repo.bump = ctx.bumps.repo;
+        repo.pending = true;
+        // Keep the requested headroom rather than shrinking to fit.
+        let space = repo.required_space()?.max(space as usize);
+        realloc_repo(
+            repo,
+            &ctx.accounts.signer.to_account_info(),
+            &ctx.accounts.system_program,
+            space,
+        )
+    }
+
+    /// Adds a chunk of branches to a pending repository.
+    pub fn append_branches(ctx: Context<ModifyRepo>, branches: Vec<BranchImport>) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        require!(repo.pending, GitError::RepoNotPending);
+        for branch in branches {
+            let commit = CommitReference::new(
+                repo.object_format,
+                branch.commit_hash,
+                branch.arweave_txs,
+                branch.storage_kind,
+                branch.content_hash,
+                None,
+            )?;
+            repo.add_branch(branch.name, commit)?;
+        }
+        if !repo.branches.is_empty() {
+            repo.record_commit()?;
+        }
+        repo.touch()?;
+        grow_repo_to_fit(repo, &ctx.accounts.owner, &ctx.accounts.system_program)
+    }
+
+    /// Ends a two-phase creation. The repository accepts pushes from now on
+    /// and no further `append_branches` calls.
+    pub fn finalize_repo(ctx: Context<ModifyRepo>) -> Result<()> {
         let repo = &mut ctx.accounts.repo;
-        repo.owner = *ctx.accounts.signer.key;
-        repo.name = name;
-        // Automatically add the owner as the first collaborator.
-        repo.collaborators.push(*ctx.accounts.signer.key);
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        require!(repo.pending, GitError::RepoNotPending);
+        repo.pending = false;
+        repo.touch()?;
         Ok(())
     }
     
     pub fn add_collaborator(ctx: Context<ModifyRepo>, new_collaborator: Pubkey) -> Result<()> {
         let repo = &mut ctx.accounts.repo;
         // Only the owner may add collaborators.
-        require!(repo.owner == *ctx.accounts.owner.key, GitError::Unauthorized);
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        repo.ensure_not_redirected()?;
+        require!(
+            !repo.collaborators.contains(&new_collaborator),
+            GitError::DuplicateCollaborator
+        );
+        require!(
+            repo.collaborators.len() < MAX_COLLABORATORS,
+            GitError::TooManyCollaborators
+        );
         repo.collaborators.push(new_collaborator);
-        Ok(())
+        repo.touch()?;
+        grow_repo_to_fit(repo, &ctx.accounts.owner, &ctx.accounts.system_program)
     }
     
-    /// Updates a branch pointer (commit_hash and arweave_tx) for a given branch.
+    /// Creates a new branch pointing at the given commit. Authorized signers
+    /// are either the repo owner or one of the collaborators.
+    pub fn create_branch(
+        ctx: Context<UpdateBranch>,
+        branch_name: String,
+        commit_hash: String,
+        arweave_tx: String,
+        storage_kind: StorageKind,
+        content_hash: [u8; 32],
+    ) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        let signer_key = *ctx.accounts.signer.key;
+        repo.ensure_collaborator(&signer_key)?;
+        repo.ensure_not_redirected()?;
+        repo.ensure_ready()?;
+        repo.ensure_stake(&signer_key, ctx.accounts.gate_token.as_deref())?;
+        let commit = CommitReference::new(
+            repo.object_format,
+            commit_hash,
+            vec![arweave_tx],
+            storage_kind,
+            content_hash,
+            None,
+        )?;
+        repo.add_branch(branch_name.clone(), commit)?;
+        repo.record_commit()?;
+        repo.touch()?;
+        let repo_key = repo.key();
+        repo.emit_branch_updated(repo_key, &branch_name, signer_key)?;
+        record_activity(ctx.accounts.activity.as_mut(), ActivityKind::BranchPushed, repo_key)?;
+        grow_repo_to_fit(repo, &ctx.accounts.signer, &ctx.accounts.system_program)
+    }
+
+    /// Updates a branch pointer (commit_hash and arweave_tx) for an existing
+    /// branch; use `create_branch` for new ones.
     /// Authorized signers are either the repo owner or one of the collaborators.
+    /// `storage_kind` says whether `arweave_tx` holds an Arweave transaction ID
+    /// or an IPFS CID. `content_hash` is the SHA-256 of the stored payload.
+    /// `diff_stat` is an optional client-computed summary of the push.
+    /// `ancestors` lists ancestor hashes of the new commit; under a
+    /// fast-forward-only push policy it must include the current tip.
+    /// `signature_arweave_tx` optionally points at a detached signature (e.g.
+    /// GPG) stored on Arweave; it is recorded, not verified.
+    /// `convention_proof` carries the repository linter's verdict on the
+    /// commit message convention; the linter must co-sign.
+    /// If the branch has required checks, a successful `CommitStatus` for
+    /// each of them on the new commit must be passed as remaining accounts.
+    /// Returns a `PushReceipt` describing the applied update.
+    #[allow(clippy::too_many_arguments)]
     pub fn update_branch(
         ctx: Context<UpdateBranch>, 
         branch_name: String, 
         commit_hash: String, 
-        arweave_tx: String
+        arweave_tx: String,
+        storage_kind: StorageKind,
+        content_hash: [u8; 32],
+        diff_stat: Option<DiffStat>,
+        ancestors: Vec<String>,
+        signature_arweave_tx: Option<String>,
+        convention_proof: Option<ConventionProof>,
+    ) -> Result<PushReceipt> {
+        update_branch_bundle(
+            ctx,
+            branch_name,
+            commit_hash,
+            vec![arweave_tx],
+            storage_kind,
+            content_hash,
+            diff_stat,
+            ancestors,
+            signature_arweave_tx,
+            convention_proof,
+        )
+    }
+
+    /// Like `update_branch`, for commits whose objects are split across up
+    /// to `MAX_BUNDLE_TXS` storage transactions, given in order.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_branch_bundle(
+        ctx: Context<UpdateBranch>,
+        branch_name: String,
+        commit_hash: String,
+        arweave_txs: Vec<String>,
+        storage_kind: StorageKind,
+        content_hash: [u8; 32],
+        diff_stat: Option<DiffStat>,
+        ancestors: Vec<String>,
+        signature_arweave_tx: Option<String>,
+        convention_proof: Option<ConventionProof>,
+    ) -> Result<PushReceipt> {
+        let repo = &mut ctx.accounts.repo;
+        let signer_key = *ctx.accounts.signer.key;
+        repo.check_push(&branch_name, &signer_key, Clock::get()?.unix_timestamp)?;
+        repo.ensure_stake(&signer_key, ctx.accounts.gate_token.as_deref())?;
+        let mut commit = CommitReference::new(
+            repo.object_format,
+            commit_hash,
+            arweave_txs,
+            storage_kind,
+            content_hash,
+            diff_stat,
+        )?
+        .with_signature(signature_arweave_tx)?;
+        let linter = ctx.accounts.linter.as_ref().map(|l| l.key());
+        commit.convention_ok = repo.check_convention(convention_proof, linter)?;
+        let push_policy = repo.settings.push_policy;
+        let repo_key = repo.key();
+        let status_key = repo.status_key;
+        let branch = repo
+            .branches
+            .iter_mut()
+            .find(|b| b.name == branch_name)
+            .ok_or(GitError::BranchNotFound)?;
+        ensure_required_checks(
+            ctx.program_id,
+            repo_key,
+            status_key,
+            &commit.commit_hash,
+            &branch.required_checks,
+            ctx.remaining_accounts,
+        )?;
+        if push_policy == PushPolicy::FastForwardOnly {
+            require!(ancestors.len() <= MAX_LINEAGE_LEN, GitError::LineageTooLong);
+            require!(
+                branch.commit.commit_hash.is_empty()
+                    || ancestors.contains(&branch.commit.commit_hash),
+                GitError::NonFastForward
+            );
+        }
+        let new_commit_hash = commit.commit_hash.clone();
+        branch.advance(commit);
+        repo.record_commit()?;
+        repo.touch()?;
+        repo.emit_branch_updated(repo_key, &branch_name, signer_key)?;
+        record_activity(ctx.accounts.activity.as_mut(), ActivityKind::BranchPushed, repo_key)?;
+        grow_repo_to_fit(repo, &ctx.accounts.signer, &ctx.accounts.system_program)?;
+        Ok(PushReceipt {
+            repo: repo_key,
+            branch: branch_name,
+            new_commit_hash,
+            slot: Clock::get()?.slot,
+            signer: signer_key,
+            event_seq: repo.event_seq,
+        })
+    }
+    
+    /// Records a merge of `source` into `target`, stored as `storage_kind`
+    /// as in `update_branch`. The target tip becomes the merge commit, whose
+    /// `parent_hash` is the previous target tip and whose `second_parent`
+    /// is the source tip; a symbolic `source` such as `HEAD` is resolved
+    /// first. With `had_conflicts`, the commit records who
+    /// resolved them: `resolver`, or else the signer. Required checks on
+    /// `target` apply to the merge commit as in `update_branch`, and if the
+    /// repository has code owners, `changed_paths` need their approval as
+    /// in `check_code_owners`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn merge_branch(
+        ctx: Context<MergeBranch>,
+        source: String,
+        target: String,
+        merge_commit_hash: String,
+        merge_arweave_tx: String,
+        storage_kind: StorageKind,
+        content_hash: [u8; 32],
+        had_conflicts: bool,
+        resolver: Option<Pubkey>,
+        changed_paths: Vec<String>,
     ) -> Result<()> {
         let repo = &mut ctx.accounts.repo;
         let signer_key = *ctx.accounts.signer.key;
-        // Check that the signer is the owner or a collaborator.
+        repo.ensure_collaborator(&signer_key)?;
+        let conflict_resolver = if had_conflicts {
+            let resolver = resolver.unwrap_or(signer_key);
+            require!(
+                repo.is_owner(&resolver) || repo.collaborators.contains(&resolver),
+                GitError::CollaboratorNotFound
+            );
+            Some(resolver)
+        } else {
+            None
+        };
+        repo.ensure_not_redirected()?;
+        repo.ensure_ready()?;
+        repo.ensure_stake(&signer_key, ctx.accounts.gate_token.as_deref())?;
+        let mut commit = CommitReference::new(
+            repo.object_format,
+            merge_commit_hash,
+            vec![merge_arweave_tx],
+            storage_kind,
+            content_hash,
+            None,
+        )?;
+        // A symbolic source such as `HEAD` merges the branch it resolves to.
+        let source_branch = repo
+            .resolve_branch(&source)
+            .ok_or(GitError::BranchNotFound)?;
+        require!(source_branch.name != target, GitError::InvalidMerge);
+        let source_tip = source_branch.commit.commit_hash.clone();
+        let repo_key = repo.key();
+        let status_key = repo.status_key;
+        let target_branch = repo
+            .branches
+            .iter_mut()
+            .find(|b| b.name == target)
+            .ok_or(GitError::BranchNotFound)?;
+        require!(!target_branch.is_symbolic(), GitError::SymbolicRefNotWritable);
+        ensure_required_checks(
+            ctx.program_id,
+            repo_key,
+            status_key,
+            &commit.commit_hash,
+            &target_branch.required_checks,
+            ctx.remaining_accounts,
+        )?;
+        if let Some(code_owners) = load_code_owners(ctx.program_id, &ctx.accounts.code_owners) {
+            ensure_code_owner_approval(&code_owners.rules, &changed_paths, ctx.remaining_accounts)?;
+        }
+        commit.parent_hash = target_branch.commit.commit_hash.clone();
+        commit.second_parent = Some(source_tip);
+        commit.conflict_resolver = conflict_resolver;
+        target_branch.advance(commit);
+        repo.record_commit()?;
+        repo.touch()?;
+        repo.emit_branch_updated(repo_key, &target, signer_key)?;
+        record_activity(ctx.accounts.activity.as_mut(), ActivityKind::BranchPushed, repo_key)?;
+        grow_repo_to_fit(repo, &ctx.accounts.signer, &ctx.accounts.system_program)
+    }
+
+    /// Creates or retargets a symbolic ref such as `HEAD` that points at
+    /// another branch instead of a commit. The target must exist, and the ref
+    /// must neither point back at itself nor chain deeper than
+    /// `MAX_SYMBOLIC_REF_DEPTH`.
+    pub fn set_symbolic_ref(ctx: Context<ModifyRepo>, name: String, target: String) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        repo.ensure_not_redirected()?;
+        // Walk the target's chain; reaching `name` would close a cycle. The
+        // ref itself is the first hop.
+        let mut current = target.clone();
+        let mut depth = 1;
+        loop {
+            require!(current != name, GitError::SymbolicRefCycle);
+            let branch = repo
+                .branches
+                .iter()
+                .find(|b| b.name == current)
+                .ok_or(GitError::BranchNotFound)?;
+            match &branch.target {
+                Some(next) => {
+                    depth += 1;
+                    require!(depth <= MAX_SYMBOLIC_REF_DEPTH, GitError::SymbolicRefTooDeep);
+                    current = next.clone();
+                }
+                None => break,
+            }
+        }
+        match repo.branches.iter_mut().find(|b| b.name == name) {
+            Some(branch) => {
+                require!(branch.is_symbolic(), GitError::BranchExists);
+                branch.target = Some(target);
+            }
+            None => {
+                require!(
+                    is_valid_branch_name(&name, repo.settings.allow_unicode_branch_names),
+                    GitError::InvalidBranchName
+                );
+                require!(
+                    repo.branches.len() < repo.settings.max_branches as usize,
+                    GitError::TooManyBranches
+                );
+                repo.branches.push(Branch {
+                    name,
+                    target: Some(target),
+                    ..Default::default()
+                })
+            }
+        }
+        repo.touch()?;
+        grow_repo_to_fit(repo, &ctx.accounts.owner, &ctx.accounts.system_program)
+    }
+
+    /// Allows the owner to update repository metadata (e.g. the repository
+    /// name or where clients should fetch objects from).
+    /// A rename moves the repository's name record: `name_record` is the
+    /// current one, needed if the repository holds one, and
+    /// `new_name_record` claims the new name, needed under the owner's name
+    /// policy. The account address keeps deriving from the original name.
+    /// The flag arguments are kept for compatibility; `update_settings` is
+    /// the general way to change settings.
+    pub fn update_repo(
+        ctx: Context<UpdateRepo>,
+        new_name: Option<String>,
+        allow_forking: Option<bool>,
+        max_branches: Option<u16>,
+        event_level: Option<u8>,
+        allow_unicode_branch_names: Option<bool>,
+        storage_config: Option<StorageConfig>,
+    ) -> Result<()> {
+        ctx.accounts.repo.ensure_owner(ctx.accounts.owner.key)?;
+        if let Some(name) = new_name {
+            require!(is_valid_repo_name(&name), GitError::InvalidRepoName);
+            let accounts = &mut *ctx.accounts;
+            let repo_key = accounts.repo.key();
+            accounts.repo.ensure_name_record(accounts.name_record.as_ref())?;
+            let required = case_insensitive_names(ctx.program_id, &accounts.name_policy);
+            let claimed = claim_name(accounts.new_name_record.as_mut(), repo_key, required)?;
+            // A case-only rename keeps the same record.
+            let new_record = accounts.new_name_record.as_ref().map(|r| r.key());
+            if let Some(old) = &accounts.name_record {
+                if new_record != Some(old.key()) {
+                    old.close(accounts.owner.to_account_info())?;
+                }
+            }
+            accounts.repo.name = name;
+            accounts.repo.name_claimed = claimed;
+        }
+        let repo = &mut ctx.accounts.repo;
+        if let Some(storage_config) = storage_config {
+            require!(storage_config.is_valid(), GitError::InvalidStorageConfig);
+            repo.storage_config = storage_config;
+        }
+        repo.settings.apply(RepoSettingsUpdate {
+            allow_forking,
+            max_branches,
+            event_level,
+            allow_unicode_branch_names,
+            push_policy: None,
+            destructive_action_delay: None,
+            emission_budget: None,
+        })?;
+        repo.touch()?;
+        grow_repo_to_fit(repo, &ctx.accounts.owner, &ctx.accounts.system_program)
+    }
+
+    /// Applies every provided field of `settings`, leaving the rest as-is.
+    pub fn update_settings(ctx: Context<UpdateRepo>, settings: RepoSettingsUpdate) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        repo.ensure_not_redirected()?;
+        repo.settings.apply(settings)?;
+        repo.touch()?;
+        Ok(())
+    }
+
+    /// Moves ownership to another key held by the same owner, e.g. from a hot
+    /// key to a cold key. Both the current and the new key must sign so the
+    /// repo cannot be handed to a key nobody controls. The new key takes the
+    /// old key's place among the collaborators; branches are left untouched.
+    /// Needs a fresh `prove_owner_control`.
+    pub fn rotate_owner_key(ctx: Context<RotateOwnerKey>) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        repo.ensure_no_timelock()?;
+        repo.consume_owner_proof(ctx.accounts.owner.key)?;
+        let old_owner = repo.owner;
+        repo.replace_owner(*ctx.accounts.new_owner.key);
+        repo.touch()?;
+        // Emitted regardless of the event level so a hijack cannot go
+        // unnoticed.
+        emit!(OwnerKeyRotatedEvent {
+            repo: repo.key(),
+            seq: repo.next_event_seq(),
+            old_owner,
+            new_owner: repo.owner,
+        });
+        Ok(())
+    }
+
+    /// Opens a staging buffer for importing collaborators in chunks across
+    /// several transactions.
+    pub fn begin_collaborator_import(ctx: Context<BeginCollaboratorImport>) -> Result<()> {
+        ctx.accounts.repo.ensure_owner(ctx.accounts.owner.key)?;
+        let import = &mut ctx.accounts.import;
+        import.repo = ctx.accounts.repo.key();
+        import.collaborators = Vec::new();
+        ctx.accounts.repo.touch()
+    }
+
+    /// Stages a chunk of collaborators, skipping keys already staged.
+    pub fn append_collaborators(
+        ctx: Context<AppendCollaborators>,
+        collaborators: Vec<Pubkey>,
+    ) -> Result<()> {
+        ctx.accounts.repo.ensure_owner(ctx.accounts.owner.key)?;
+        let import = &mut ctx.accounts.import;
+        for key in collaborators {
+            if !import.collaborators.contains(&key) {
+                require!(
+                    import.collaborators.len() < MAX_IMPORT_COLLABORATORS,
+                    GitError::ImportBufferFull
+                );
+                import.collaborators.push(key);
+            }
+        }
+        ctx.accounts.repo.touch()
+    }
+
+    /// Applies every staged collaborator to the repository in one step and
+    /// closes the buffer. Keys that are already collaborators are skipped.
+    pub fn commit_collaborator_import(ctx: Context<FinishCollaboratorImport>) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        repo.ensure_not_redirected()?;
+        for key in ctx.accounts.import.collaborators.iter() {
+            if !repo.collaborators.contains(key) {
+                require!(
+                    repo.collaborators.len() < MAX_COLLABORATORS,
+                    GitError::TooManyCollaborators
+                );
+                repo.collaborators.push(*key);
+            }
+        }
+        repo.touch()?;
+        grow_repo_to_fit(repo, &ctx.accounts.owner, &ctx.accounts.system_program)
+    }
+
+    /// Discards a pending import and reclaims the buffer's rent.
+    pub fn cancel_collaborator_import(ctx: Context<FinishCollaboratorImport>) -> Result<()> {
+        ctx.accounts.repo.ensure_owner(ctx.accounts.owner.key)?;
+        ctx.accounts.repo.touch()
+    }
+
+    /// Returns one chunk of the Borsh-serialized `RepoExport` for this repo.
+    /// Clients simulate this for chunks `0..total_chunks` and concatenate the
+    /// bytes, which keeps them independent of the raw account layout.
+    pub fn export_repo(ctx: Context<ReadRepo>, chunk: u16) -> Result<RepoExportChunk> {
+        let repo = &ctx.accounts.repo;
+        let export = RepoExport {
+            version: REPO_EXPORT_VERSION,
+            owner: repo.owner,
+            name: repo.name.clone(),
+            collaborators: repo.collaborators.clone(),
+            branches: repo
+                .branches
+                .iter()
+                .filter(|b| !b.ephemeral)
+                .map(|b| ExportedBranch {
+                    name: b.name.clone(),
+                    commit_hash: b.commit.commit_hash.clone(),
+                    storage_ids: b.commit.arweave_txs.clone(),
+                    storage_kind: b.commit.storage_kind,
+                })
+                .collect(),
+            pinned: repo.pinned.clone(),
+            last_activity: repo.repo_last_activity,
+        };
+        let bytes = export.try_to_vec()?;
+        let total_chunks = bytes.len().div_ceil(EXPORT_CHUNK_SIZE).max(1);
         require!(
-            repo.owner == signer_key || repo.collaborators.contains(&signer_key),
-            GitError::Unauthorized
+            (chunk as usize) < total_chunks,
+            GitError::ExportChunkOutOfRange
         );
-        // Look for the branch and update if it exists.
-        let mut branch_found = false;
-        for branch in repo.branches.iter_mut() {
-            if branch.name == branch_name {
-                branch.commit.commit_hash = commit_hash.clone();
-                branch.commit.arweave_tx = arweave_tx.clone();
-                branch_found = true;
-                break;
-            }
-        }
-        // If the branch doesn't exist, create a new branch entry.
-        if !branch_found {
-            let new_branch = Branch {
-                name: branch_name,
-                commit: CommitReference {
-                    commit_hash,
-                    arweave_tx,
-                },
-            };
-            repo.branches.push(new_branch);
+        let start = chunk as usize * EXPORT_CHUNK_SIZE;
+        let end = (start + EXPORT_CHUNK_SIZE).min(bytes.len());
+        Ok(RepoExportChunk {
+            total_chunks: total_chunks as u16,
+            chunk,
+            data: bytes[start..end].to_vec(),
+        })
+    }
+
+    /// Returns one page of the repository's branches in a stable layout.
+    /// Clients simulate this for pages `0..total_pages`. Ephemeral refs are
+    /// left out, as in `export_repo`, and so are drafts unless
+    /// `include_drafts` is set.
+    pub fn list_branches_data(
+        ctx: Context<ReadRepo>,
+        page: u16,
+        include_drafts: bool,
+    ) -> Result<BranchPage> {
+        let branches: Vec<&Branch> = ctx
+            .accounts
+            .repo
+            .branches
+            .iter()
+            .filter(|b| !b.ephemeral && (include_drafts || !b.draft))
+            .collect();
+        let total_pages = branches.len().div_ceil(BRANCH_PAGE_SIZE).max(1);
+        require!(
+            (page as usize) < total_pages,
+            GitError::BranchPageOutOfRange
+        );
+        Ok(BranchPage {
+            total_pages: total_pages as u16,
+            page,
+            branches: branches
+                .iter()
+                .skip(page as usize * BRANCH_PAGE_SIZE)
+                .take(BRANCH_PAGE_SIZE)
+                .map(|b| ListedBranch {
+                    name: b.name.clone(),
+                    commit_hash: b.commit.commit_hash.clone(),
+                    target: b.target.clone(),
+                    draft: b.draft,
+                })
+                .collect(),
+        })
+    }
+
+    /// Pins an existing branch so clients list it ahead of the others.
+    /// Pinning an already pinned branch is a no-op.
+    pub fn pin_branch(ctx: Context<ModifyRepo>, branch_name: String) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        repo.ensure_not_redirected()?;
+        require!(
+            repo.branches.iter().any(|b| b.name == branch_name),
+            GitError::BranchNotFound
+        );
+        if repo.pinned.contains(&branch_name) {
+            return Ok(());
         }
+        require!(
+            repo.pinned.len() < MAX_PINNED_BRANCHES,
+            GitError::TooManyPinnedBranches
+        );
+        repo.pinned.push(branch_name);
+        repo.touch()?;
+        grow_repo_to_fit(repo, &ctx.accounts.owner, &ctx.accounts.system_program)
+    }
+
+    /// Removes a branch from the pinned list.
+    pub fn unpin_branch(ctx: Context<ModifyRepo>, branch_name: String) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        repo.ensure_not_redirected()?;
+        repo.pinned.retain(|name| *name != branch_name);
+        repo.touch()?;
         Ok(())
     }
-    
-    /// Allows the owner to update repository metadata (e.g. the repository name).
-    pub fn update_repo(ctx: Context<UpdateRepo>, new_name: Option<String>) -> Result<()> {
+
+    /// Registers (or replaces) the off-chain notification target for a repo.
+    /// `endpoint` is an opaque identifier such as a hashed URL or a relayer
+    /// pubkey; `events` is a bitmask of the event kinds the relayer forwards.
+    pub fn register_notification(
+        ctx: Context<RegisterNotification>,
+        endpoint: [u8; 32],
+        events: u32,
+    ) -> Result<()> {
+        ctx.accounts.repo.ensure_owner(ctx.accounts.owner.key)?;
+        let target = &mut ctx.accounts.target;
+        target.repo = ctx.accounts.repo.key();
+        target.endpoint = endpoint;
+        target.events = events;
+        if ctx.accounts.repo.emits(EVENT_LEVEL_ALL) {
+            emit!(NotificationRegisteredEvent {
+                repo: target.repo,
+                seq: ctx.accounts.repo.next_event_seq(),
+                endpoint,
+                events,
+            });
+        }
+        ctx.accounts.repo.touch()
+    }
+
+    /// Removes the notification target and reclaims its rent.
+    pub fn unregister_notification(ctx: Context<UnregisterNotification>) -> Result<()> {
+        ctx.accounts.repo.ensure_owner(ctx.accounts.owner.key)?;
+        if ctx.accounts.repo.emits(EVENT_LEVEL_ALL) {
+            emit!(NotificationUnregisteredEvent {
+                repo: ctx.accounts.repo.key(),
+                seq: ctx.accounts.repo.next_event_seq(),
+            });
+        }
+        ctx.accounts.repo.touch()
+    }
+
+    /// Points users at the repository's new location. While a redirect is
+    /// set, every instruction that calls `touch` fails with
+    /// `RepoRedirected`, leaving the repository read-only until
+    /// `clear_redirect`. To retarget, clear the redirect first.
+    pub fn set_redirect(ctx: Context<ModifyRepo>, target: RepoId) -> Result<()> {
         let repo = &mut ctx.accounts.repo;
-        require!(repo.owner == *ctx.accounts.owner.key, GitError::Unauthorized);
-        if let Some(name) = new_name {
-            repo.name = name;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        require!(target.is_valid(), GitError::InvalidRepoName);
+        require!(
+            !(target.owner == repo.owner && target.name == repo.name),
+            GitError::InvalidRedirect
+        );
+        // Touch first: once the redirect is set, `touch` rejects the repo.
+        repo.touch()?;
+        repo.redirect = Some(target);
+        grow_repo_to_fit(repo, &ctx.accounts.owner, &ctx.accounts.system_program)
+    }
+
+    /// Removes the redirect, making the repository writable again.
+    pub fn clear_redirect(ctx: Context<ModifyRepo>) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        repo.redirect = None;
+        repo.touch()?;
+        Ok(())
+    }
+
+    /// Closes the repository and its name record, if it holds one,
+    /// returning their rent and the creation deposit to `owner`, even when
+    /// a co-owner signs. Needs a fresh `prove_owner_control` by the signer.
+    pub fn close_repo(ctx: Context<CloseRepo>) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        repo.ensure_no_timelock()?;
+        repo.ensure_name_record(ctx.accounts.name_record.as_ref())?;
+        repo.consume_owner_proof(ctx.accounts.owner.key)
+    }
+
+    /// Replaces the repository's code owners mapping.
+    pub fn set_code_owners(ctx: Context<SetCodeOwners>, rules: Vec<CodeOwnerRule>) -> Result<()> {
+        ctx.accounts.repo.ensure_owner(ctx.accounts.owner.key)?;
+        require!(rules.len() <= MAX_CODE_OWNER_RULES, GitError::InvalidCodeOwners);
+        for rule in rules.iter() {
+            require!(
+                !rule.path_prefix.is_empty() && rule.path_prefix.len() <= MAX_PATH_PREFIX_LEN,
+                GitError::InvalidCodeOwners
+            );
+            require!(
+                !rule.owners.is_empty() && rule.owners.len() <= MAX_OWNERS_PER_RULE,
+                GitError::InvalidCodeOwners
+            );
+        }
+        let code_owners = &mut ctx.accounts.code_owners;
+        code_owners.repo = ctx.accounts.repo.key();
+        code_owners.rules = rules;
+        ctx.accounts.repo.touch()
+    }
+
+    /// Fails unless every changed path has approval from one of its code
+    /// owners. The path list is supplied by the client; approvers sign the
+    /// transaction and are passed as remaining accounts. Each path is
+    /// governed by the rule with the longest matching prefix, and paths
+    /// that match no rule need no approval. `merge_branch` runs the same
+    /// check itself.
+    pub fn check_code_owners(ctx: Context<CheckCodeOwners>, changed_paths: Vec<String>) -> Result<()> {
+        ensure_code_owner_approval(
+            &ctx.accounts.code_owners.rules,
+            &changed_paths,
+            ctx.remaining_accounts,
+        )
+    }
+
+    /// Removes placeholder branches: those never pushed to since they were
+    /// created at the default branch's tip, which they still point at. The
+    /// default branch, any branch a symbolic ref points at and protected
+    /// branches are kept.
+    pub fn prune_empty_branches(ctx: Context<UpdateBranch>) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        let signer_key = *ctx.accounts.signer.key;
+        repo.ensure_collaborator(&signer_key)?;
+        repo.ensure_not_redirected()?;
+        let default_tip = repo
+            .resolve_branch(HEAD_REF)
+            .map(|b| b.commit.commit_hash.clone());
+        let pruned: Vec<String> = repo
+            .branches
+            .iter()
+            .filter(|b| !b.is_symbolic() && !b.is_protected() && b.recent_tips.is_empty())
+            .filter(|b| default_tip.as_ref() == Some(&b.commit.commit_hash))
+            .filter(|b| !repo.is_ref_target(&b.name))
+            .map(|b| b.name.clone())
+            .collect();
+        repo.remove_branches(&pruned);
+        repo.touch()?;
+        if repo.emits(EVENT_LEVEL_BRANCHES) {
+            emit!(BranchesPrunedEvent {
+                repo: repo.key(),
+                seq: repo.next_event_seq(),
+                names: pruned,
+            });
         }
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-#[instruction(name: String)]
-pub struct CreateRepo<'info> {
-    #[account(
-        init,
-        payer = signer,
-        space = 9000,
-        seeds = [b"repository", signer.key().as_ref(), name.as_bytes()],
-        bump
-    )]
-    pub repo: Account<'info, Repository>,
-    #[account(mut)]
-    pub signer: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    /// Shrinks the repository account to fit its current contents and
+    /// refunds the freed rent to `owner`, even when a co-owner signs.
+    pub fn shrink_repo(ctx: Context<ShrinkRepo>) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        repo.touch()?;
+        let space = repo.required_space()?;
+        if space < repo.to_account_info().data_len() {
+            realloc_repo(
+                repo,
+                &ctx.accounts.payee.to_account_info(),
+                &ctx.accounts.system_program,
+                space,
+            )?;
+        }
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct ModifyRepo<'info> {
-    #[account(mut, has_one = owner)]
-    pub repo: Account<'info, Repository>,
-    #[account(mut)]
-    pub owner: Signer<'info>,
-}
+    /// Creates the program-wide configuration. Only the program's upgrade
+    /// authority may do this, and it becomes the config admin.
+    pub fn initialize_config(ctx: Context<InitializeConfig>, moderator: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.moderator = moderator;
+        config.default_repo_space = REPO_INITIAL_SPACE as u32;
+        config.creation_deposit = MIN_CREATION_DEPOSIT;
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct UpdateBranch<'info> {
-    #[account(mut)]
-    pub repo: Account<'info, Repository>,
-    #[account(mut)]
-    pub signer: Signer<'info>,
-}
+    /// Applies every provided field of `update` to the program config.
+    pub fn update_config(ctx: Context<UpdateConfig>, update: ConfigUpdate) -> Result<()> {
+        ctx.accounts.config.apply(update)
+    }
 
-#[derive(Accounts)]
-pub struct UpdateRepo<'info> {
-    #[account(mut, has_one = owner)]
-    pub repo: Account<'info, Repository>,
-    #[account(mut)]
-    pub owner: Signer<'info>,
-}
+    /// Flags a repository for moderator review. Enforcement happens off-chain;
+    /// the program only records the report and announces it.
+    pub fn report_repository(ctx: Context<ReportRepository>, reason_code: u8) -> Result<()> {
+        let report = &mut ctx.accounts.report;
+        report.reporter = ctx.accounts.reporter.key();
+        report.repo = ctx.accounts.repo.key();
+        report.reason_code = reason_code;
+        report.created_at = Clock::get()?.unix_timestamp;
+        report.resolved = false;
+        // Reports are emitted regardless of the repository's event level so
+        // an owner cannot silence moderation.
+        emit!(ReportEvent {
+            repo: report.repo,
+            seq: ctx.accounts.repo.next_event_seq(),
+            reporter: report.reporter,
+            reason_code,
+        });
+        Ok(())
+    }
 
-#[account]
-pub struct Repository {
-    pub owner: Pubkey,
-    pub name: String,
-    pub collaborators: Vec<Pubkey>,
-    pub branches: Vec<Branch>,
-}
+    /// Marks a report as handled. Only the configured moderator may resolve.
+    pub fn resolve_report(ctx: Context<ResolveReport>) -> Result<()> {
+        let report = &mut ctx.accounts.report;
+        require!(!report.resolved, GitError::ReportAlreadyResolved);
+        report.resolved = true;
+        emit!(ReportResolvedEvent {
+            repo: report.repo,
+            seq: ctx.accounts.repo.next_event_seq(),
+            reporter: report.reporter,
+            moderator: ctx.accounts.moderator.key(),
+        });
+        Ok(())
+    }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct Branch {
-    pub name: String,
-    pub commit: CommitReference,
-}
+    /// Emits the canonical clone reference for the repository so every
+    /// remote helper addresses it the same way. Read-only: the event does not
+    /// advance `event_seq`. `bump` is `None` once the owner key has been
+    /// rotated, since the address no longer derives from the current owner.
+    pub fn get_clone_ref(ctx: Context<ReadRepo>) -> Result<()> {
+        let repo = &ctx.accounts.repo;
+        let pda = Pubkey::create_program_address(
+            &[b"repository", repo.owner.as_ref(), repo.name.as_bytes(), &[repo.bump]],
+            ctx.program_id,
+        );
+        emit!(CloneRefEvent {
+            repo: repo.key(),
+            bump: (pda == Ok(repo.key())).then_some(repo.bump),
+            clone_ref: repo.clone_ref(),
+            default_branch: repo.default_branch().map(str::to_string),
+        });
+        Ok(())
+    }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct CommitReference {
-    pub commit_hash: String,
-    pub arweave_tx: String,
-}
+    /// Emits the inclusion proof of `branch_name` against
+    /// `branch_tips_root`. Read-only: the event does not advance
+    /// `event_seq`.
+    pub fn get_branch_proof(ctx: Context<ReadRepo>, branch_name: String) -> Result<()> {
+        let repo = &ctx.accounts.repo;
+        let (index, leaf_count, proof) = repo
+            .branch_proof(&branch_name)
+            .ok_or(GitError::BranchNotFound)?;
+        emit!(BranchProofEvent {
+            repo: repo.key(),
+            branch: branch_name,
+            root: repo.branch_tips_root,
+            index,
+            leaf_count,
+            proof,
+        });
+        Ok(())
+    }
+
+    /// Records how far a branch has diverged from the default branch. The
+    /// counts are computed by the client; the program only stores the latest
+    /// values.
+    pub fn set_branch_divergence(
+        ctx: Context<UpdateBranch>,
+        branch_name: String,
+        ahead: u32,
+        behind: u32,
+    ) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        let signer_key = *ctx.accounts.signer.key;
+        repo.ensure_collaborator(&signer_key)?;
+        let branch = repo
+            .branches
+            .iter_mut()
+            .find(|b| b.name == branch_name)
+            .ok_or(GitError::BranchNotFound)?;
+        require!(!branch.is_symbolic(), GitError::SymbolicRefNotWritable);
+        branch.ahead = ahead;
+        branch.behind = behind;
+        repo.touch()?;
+        Ok(())
+    }
+
+    /// Schedules a destructive action to run once the repository's
+    /// `destructive_action_delay` has elapsed. Rotating the owner key also
+    /// needs the new key's signature here, as with `rotate_owner_key`.
+    pub fn request_destructive_action(
+        ctx: Context<RequestDestructiveAction>,
+        action: DestructiveAction,
+    ) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        match &action {
+            DestructiveAction::RotateOwnerKey { new_owner } => require!(
+                ctx.accounts.new_owner.as_ref().map(|k| k.key()) == Some(*new_owner),
+                GitError::Unauthorized
+            ),
+            DestructiveAction::LowerDelay { delay } => require!(
+                *delay < repo.settings.destructive_action_delay,
+                GitError::InvalidTimelock
+            ),
+            DestructiveAction::CloseRepo => {}
+        }
+        let execute_after = Clock::get()?
+            .unix_timestamp
+            .checked_add(repo.settings.destructive_action_delay as i64)
+            .ok_or(GitError::InvalidTimelock)?;
+        let pending = &mut ctx.accounts.pending;
+        pending.repo = repo.key();
+        pending.action = action.clone();
+        pending.execute_after = execute_after;
+        // Announced regardless of the event level: the delay only helps if
+        // watchers can see what is coming.
+        emit!(DestructiveActionRequestedEvent {
+            repo: pending.repo,
+            seq: repo.next_event_seq(),
+            action,
+            execute_after,
+        });
+        repo.touch()
+    }
+
+    /// Runs a scheduled destructive action once its timelock has passed.
+    /// Needs a `prove_owner_control` by the signer made shortly before
+    /// execution, not at request time. A scheduled close pays out to
+    /// `owner`, as `close_repo` does.
+    pub fn execute_destructive_action(ctx: Context<ExecuteDestructiveAction>) -> Result<()> {
+        ctx.accounts.repo.ensure_owner(ctx.accounts.owner.key)?;
+        require!(
+            Clock::get()?.unix_timestamp >= ctx.accounts.pending.execute_after,
+            GitError::TimelockActive
+        );
+        ctx.accounts.repo.consume_owner_proof(ctx.accounts.owner.key)?;
+        match ctx.accounts.pending.action.clone() {
+            DestructiveAction::CloseRepo => {
+                let payee = ctx.accounts.payee.to_account_info();
+                ctx.accounts
+                    .repo
+                    .ensure_name_record(ctx.accounts.name_record.as_ref())?;
+                if let Some(name_record) = &ctx.accounts.name_record {
+                    name_record.close(payee.clone())?;
+                }
+                ctx.accounts.repo.close(payee)?;
+            }
+            DestructiveAction::RotateOwnerKey { new_owner } => {
+                let repo = &mut ctx.accounts.repo;
+                let old_owner = repo.owner;
+                repo.replace_owner(new_owner);
+                repo.touch()?;
+                emit!(OwnerKeyRotatedEvent {
+                    repo: repo.key(),
+                    seq: repo.next_event_seq(),
+                    old_owner,
+                    new_owner,
+                });
+            }
+            DestructiveAction::LowerDelay { delay } => {
+                let repo = &mut ctx.accounts.repo;
+                repo.settings.destructive_action_delay = delay;
+                repo.touch()?;
+            }
+        }
+        Ok(())
+    }
 
-#[error_code]
-pub enum GitError {
-    #[msg("You are not authorized to perform this action.")]
-    Unauthorized,
+    /// Aborts a scheduled destructive action and reclaims its rent.
+    pub fn cancel_destructive_action(ctx: Context<CancelDestructiveAction>) -> Result<()> {
+        ctx.accounts.repo.ensure_owner(ctx.accounts.owner.key)?;
+        ctx.accounts.repo.touch()
+    }
+
+    /// Deliberately moves a branch back to an earlier commit, recording why.
+    /// Under a fast-forward-only push policy only the owner may reset, and
+    /// only with `force` set. Required checks apply to the target commit as
+    /// in `update_branch`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn reset_branch(
+        ctx: Context<UpdateBranch>,
+        branch_name: String,
+        target_hash: String,
+        target_arweave_tx: String,
+        storage_kind: StorageKind,
+        content_hash: [u8; 32],
+        reason: String,
+        force: bool,
+    ) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        let signer_key = *ctx.accounts.signer.key;
+        repo.ensure_collaborator(&signer_key)?;
+        repo.ensure_not_redirected()?;
+        repo.ensure_ready()?;
+        repo.ensure_stake(&signer_key, ctx.accounts.gate_token.as_deref())?;
+        if repo.settings.push_policy == PushPolicy::FastForwardOnly {
+            repo.ensure_owner(&signer_key)?;
+            require!(force, GitError::NonFastForward);
+        }
+        require!(
+            !reason.is_empty() && reason.len() <= MAX_RESET_REASON_LEN,
+            GitError::InvalidResetReason
+        );
+        let commit = CommitReference::new(
+            repo.object_format,
+            target_hash,
+            vec![target_arweave_tx],
+            storage_kind,
+            content_hash,
+            None,
+        )?;
+        let repo_key = repo.key();
+        let status_key = repo.status_key;
+        let branch = repo
+            .branches
+            .iter_mut()
+            .find(|b| b.name == branch_name)
+            .ok_or(GitError::BranchNotFound)?;
+        require!(!branch.is_symbolic(), GitError::SymbolicRefNotWritable);
+        ensure_required_checks(
+            ctx.program_id,
+            repo_key,
+            status_key,
+            &commit.commit_hash,
+            &branch.required_checks,
+            ctx.remaining_accounts,
+        )?;
+        let previous_hash = branch.advance(commit).commit_hash;
+        let commit_hash = branch.commit.commit_hash.clone();
+        repo.touch()?;
+        // Always emitted: resets are the audit trail for rewritten history.
+        emit!(BranchResetEvent {
+            repo: repo_key,
+            seq: repo.next_event_seq(),
+            branch: branch_name,
+            previous_hash,
+            commit_hash,
+            reason,
+            signer: signer_key,
+        });
+        grow_repo_to_fit(repo, &ctx.accounts.signer, &ctx.accounts.system_program)
+    }
+
+    /// Whether the repository has seen no mutating instruction for at least
+    /// `threshold_seconds`, for cleanup tooling that simulates one call per
+    /// repository.
+    pub fn is_stale(ctx: Context<ReadRepo>, threshold_seconds: i64) -> Result<bool> {
+        require!(threshold_seconds > 0, GitError::InvalidThreshold);
+        let idle = Clock::get()?
+            .unix_timestamp
+            .saturating_sub(ctx.accounts.repo.repo_last_activity);
+        Ok(idle >= threshold_seconds)
+    }
+
+    /// Sets or, with `None`, clears the owner's note on a collaborator.
+    pub fn set_collaborator_note(
+        ctx: Context<ModifyRepo>,
+        collaborator: Pubkey,
+        note: Option<String>,
+    ) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        require!(
+            repo.collaborators.contains(&collaborator),
+            GitError::CollaboratorNotFound
+        );
+        repo.collaborator_notes.retain(|n| n.collaborator != collaborator);
+        if let Some(note) = note {
+            require!(
+                !note.is_empty() && note.len() <= MAX_COLLABORATOR_NOTE_LEN,
+                GitError::InvalidCollaboratorNote
+            );
+            repo.collaborator_notes.push(CollaboratorNote { collaborator, note });
+        }
+        repo.touch()?;
+        grow_repo_to_fit(repo, &ctx.accounts.owner, &ctx.accounts.system_program)
+    }
+
+    /// Renames the default branch and repoints `HEAD`, every other symbolic
+    /// ref and any pin at the new name in one step.
+    pub fn rename_default_branch(ctx: Context<ModifyRepo>, new_name: String) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        repo.ensure_not_redirected()?;
+        let old_name = repo
+            .default_branch()
+            .map(str::to_string)
+            .ok_or(GitError::BranchNotFound)?;
+        require!(
+            is_valid_branch_name(&new_name, repo.settings.allow_unicode_branch_names),
+            GitError::InvalidBranchName
+        );
+        require!(
+            !repo.branches.iter().any(|b| b.name == new_name),
+            GitError::BranchExists
+        );
+        for branch in repo.branches.iter_mut() {
+            if branch.name == old_name {
+                branch.name = new_name.clone();
+            } else if branch.target.as_deref() == Some(old_name.as_str()) {
+                branch.target = Some(new_name.clone());
+            }
+        }
+        for pinned in repo.pinned.iter_mut() {
+            if *pinned == old_name {
+                *pinned = new_name.clone();
+            }
+        }
+        repo.touch()?;
+        let repo_key = repo.key();
+        repo.emit_branch_updated(repo_key, &new_name, ctx.accounts.owner.key())?;
+        grow_repo_to_fit(repo, &ctx.accounts.owner, &ctx.accounts.system_program)
+    }
+
+    /// Sets or, with `None`, clears the key that may call
+    /// `set_verified_commit` besides the owner.
+    pub fn set_verifier(ctx: Context<ModifyRepo>, verifier: Option<Pubkey>) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        repo.verifier = verifier;
+        repo.touch()?;
+        grow_repo_to_fit(repo, &ctx.accounts.owner, &ctx.accounts.system_program)
+    }
+
+    /// Records that `commit_hash` passed the repository's checks on
+    /// `branch_name`. Only the owner or the configured verifier may call
+    /// this, and the commit must be the branch tip or one of its last
+    /// `MAX_RECENT_TIPS` tips.
+    pub fn set_verified_commit(
+        ctx: Context<UpdateBranch>,
+        branch_name: String,
+        commit_hash: String,
+    ) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        let signer_key = *ctx.accounts.signer.key;
+        require!(
+            repo.is_owner(&signer_key) || repo.verifier == Some(signer_key),
+            GitError::Unauthorized
+        );
+        let branch = repo
+            .branches
+            .iter_mut()
+            .find(|b| b.name == branch_name)
+            .ok_or(GitError::BranchNotFound)?;
+        require!(!branch.is_symbolic(), GitError::SymbolicRefNotWritable);
+        require!(
+            !commit_hash.is_empty()
+                && (branch.commit.commit_hash == commit_hash
+                    || branch.recent_tips.contains(&commit_hash)),
+            GitError::CommitNotRecentTip
+        );
+        branch.last_verified_commit = commit_hash.clone();
+        repo.touch()?;
+        if repo.emits(EVENT_LEVEL_BRANCHES) {
+            emit!(CommitVerifiedEvent {
+                repo: repo.key(),
+                seq: repo.next_event_seq(),
+                branch: branch_name,
+                commit_hash,
+                verifier: signer_key,
+            });
+        }
+        grow_repo_to_fit(repo, &ctx.accounts.signer, &ctx.accounts.system_program)
+    }
+
+    /// Whether `key` may push to `branch_name` right now, and if not, why.
+    /// Runs the same checks as `update_branch`, so clients can ask instead
+    /// of reimplementing them. The stake gate is not covered, as it depends
+    /// on the key's token account.
+    pub fn can_push(
+        ctx: Context<ReadRepo>,
+        branch_name: String,
+        key: Pubkey,
+    ) -> Result<PushPermission> {
+        let now = Clock::get()?.unix_timestamp;
+        let denial = ctx.accounts.repo.check_push(&branch_name, &key, now).err();
+        Ok(PushPermission {
+            allowed: denial.is_none(),
+            denial,
+        })
+    }
+
+    /// Creates or moves a throwaway ref, e.g. a CI preview build. Ephemeral
+    /// refs authorize like normal pushes but are left out of `export_repo`
+    /// and removed wholesale by `prune_ephemeral_refs`. An existing
+    /// non-ephemeral branch of the same name is not touched. Required checks
+    /// on an existing ref apply as in `update_branch`.
+    pub fn push_ephemeral_ref(
+        ctx: Context<UpdateBranch>,
+        name: String,
+        commit_hash: String,
+        arweave_tx: String,
+        storage_kind: StorageKind,
+        content_hash: [u8; 32],
+    ) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        let signer_key = *ctx.accounts.signer.key;
+        repo.ensure_collaborator(&signer_key)?;
+        repo.ensure_not_redirected()?;
+        repo.ensure_ready()?;
+        repo.ensure_stake(&signer_key, ctx.accounts.gate_token.as_deref())?;
+        let commit = CommitReference::new(
+            repo.object_format,
+            commit_hash,
+            vec![arweave_tx],
+            storage_kind,
+            content_hash,
+            None,
+        )?;
+        let repo_key = repo.key();
+        let status_key = repo.status_key;
+        match repo.branches.iter_mut().find(|b| b.name == name) {
+            Some(branch) => {
+                require!(branch.ephemeral, GitError::BranchExists);
+                ensure_required_checks(
+                    ctx.program_id,
+                    repo_key,
+                    status_key,
+                    &commit.commit_hash,
+                    &branch.required_checks,
+                    ctx.remaining_accounts,
+                )?;
+                branch.advance(commit);
+            }
+            None => {
+                repo.add_branch(name.clone(), commit)?;
+                if let Some(branch) = repo.branches.last_mut() {
+                    branch.ephemeral = true;
+                }
+            }
+        }
+        repo.touch()?;
+        repo.emit_branch_updated(repo_key, &name, signer_key)?;
+        record_activity(ctx.accounts.activity.as_mut(), ActivityKind::BranchPushed, repo_key)?;
+        grow_repo_to_fit(repo, &ctx.accounts.signer, &ctx.accounts.system_program)
+    }
+
+    /// Removes every ephemeral ref that no symbolic ref points at, except
+    /// protected ones.
+    pub fn prune_ephemeral_refs(ctx: Context<UpdateBranch>) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        let signer_key = *ctx.accounts.signer.key;
+        repo.ensure_collaborator(&signer_key)?;
+        repo.ensure_not_redirected()?;
+        let pruned: Vec<String> = repo
+            .branches
+            .iter()
+            .filter(|b| b.ephemeral && !b.is_protected() && !repo.is_ref_target(&b.name))
+            .map(|b| b.name.clone())
+            .collect();
+        repo.remove_branches(&pruned);
+        repo.touch()?;
+        if repo.emits(EVENT_LEVEL_BRANCHES) {
+            emit!(BranchesPrunedEvent {
+                repo: repo.key(),
+                seq: repo.next_event_seq(),
+                names: pruned,
+            });
+        }
+        Ok(())
+    }
+
+    /// Requires collaborators other than the owners to hold at least
+    /// `min_balance` of `gate_mint` to move branch tips, whether by push,
+    /// create, merge, reset or swap. `None` removes the gate.
+    pub fn set_stake_gate(
+        ctx: Context<ModifyRepo>,
+        gate_mint: Option<Pubkey>,
+        min_balance: u64,
+    ) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        repo.gate_mint = gate_mint;
+        repo.min_balance = if gate_mint.is_some() { min_balance } else { 0 };
+        repo.touch()?;
+        grow_repo_to_fit(repo, &ctx.accounts.owner, &ctx.accounts.system_program)
+    }
+
+    /// Sets the hash of the agreed commit message convention and the linter
+    /// key trusted to attest to it on pushes. `None` for `convention` turns
+    /// attestation off.
+    pub fn set_commit_convention(
+        ctx: Context<ModifyRepo>,
+        convention: Option<[u8; 32]>,
+        linter: Option<Pubkey>,
+    ) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        repo.commit_convention = convention;
+        repo.linter = linter;
+        repo.touch()?;
+        grow_repo_to_fit(repo, &ctx.accounts.owner, &ctx.accounts.system_program)
+    }
+
+    /// Exchanges the tips of two branches in one step, e.g. to promote
+    /// `staging` to `live` and keep the old release one swap away. A swap
+    /// rewrites history like `reset_branch`, so under a fast-forward-only
+    /// push policy only the owner may swap, and only with `force` set.
+    /// Each branch's required checks apply to the tip it receives, with the
+    /// statuses passed as remaining accounts as in `update_branch`.
+    pub fn swap_branches(
+        ctx: Context<UpdateBranch>,
+        branch_a: String,
+        branch_b: String,
+        force: bool,
+    ) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        let signer_key = *ctx.accounts.signer.key;
+        repo.ensure_collaborator(&signer_key)?;
+        repo.ensure_not_redirected()?;
+        repo.ensure_ready()?;
+        repo.ensure_stake(&signer_key, ctx.accounts.gate_token.as_deref())?;
+        if repo.settings.push_policy == PushPolicy::FastForwardOnly {
+            repo.ensure_owner(&signer_key)?;
+            require!(force, GitError::NonFastForward);
+        }
+        require!(branch_a != branch_b, GitError::InvalidSwap);
+        let index_a = repo
+            .branches
+            .iter()
+            .position(|b| b.name == branch_a)
+            .ok_or(GitError::BranchNotFound)?;
+        let index_b = repo
+            .branches
+            .iter()
+            .position(|b| b.name == branch_b)
+            .ok_or(GitError::BranchNotFound)?;
+        require!(
+            !repo.branches[index_a].is_symbolic() && !repo.branches[index_b].is_symbolic(),
+            GitError::SymbolicRefNotWritable
+        );
+        let repo_key = repo.key();
+        let status_key = repo.status_key;
+        for (to, from) in [(index_a, index_b), (index_b, index_a)] {
+            ensure_required_checks(
+                ctx.program_id,
+                repo_key,
+                status_key,
+                &repo.branches[from].commit.commit_hash,
+                &repo.branches[to].required_checks,
+                ctx.remaining_accounts,
+            )?;
+        }
+        let commit_b = repo.branches[index_b].commit.clone();
+        let commit_a = repo.branches[index_a].advance(commit_b);
+        repo.branches[index_b].advance(commit_a);
+        repo.touch()?;
+        if repo.emits(EVENT_LEVEL_BRANCHES) {
+            emit!(BranchSwappedEvent {
+                repo: repo_key,
+                seq: repo.next_event_seq(),
+                branch_a,
+                branch_b,
+                signer: signer_key,
+            });
+        }
+        grow_repo_to_fit(repo, &ctx.accounts.signer, &ctx.accounts.system_program)
+    }
+
+    /// Lists another repository as a mirror that clients can fall back to
+    /// when this one's data is unavailable. Adding a listed mirror is a
+    /// no-op.
+    pub fn add_mirror(ctx: Context<ModifyRepo>, mirror: RepoId) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        require!(mirror.is_valid(), GitError::InvalidRepoName);
+        require!(
+            !(mirror.owner == repo.owner && mirror.name == repo.name),
+            GitError::InvalidMirror
+        );
+        if repo.mirrors.contains(&mirror) {
+            return Ok(());
+        }
+        require!(repo.mirrors.len() < MAX_MIRRORS, GitError::TooManyMirrors);
+        repo.mirrors.push(mirror);
+        repo.touch()?;
+        grow_repo_to_fit(repo, &ctx.accounts.owner, &ctx.accounts.system_program)
+    }
+
+    /// Removes a repository from the mirror list.
+    pub fn remove_mirror(ctx: Context<ModifyRepo>, mirror: RepoId) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        repo.mirrors.retain(|m| *m != mirror);
+        repo.touch()?;
+        Ok(())
+    }
+
+    /// Opts the signer in to, or out of, recording their actions in their
+    /// public activity feed. Opting out keeps the existing entries.
+    pub fn set_activity_feed(ctx: Context<SetActivityFeed>, enabled: bool) -> Result<()> {
+        let activity = &mut ctx.accounts.activity;
+        activity.user = ctx.accounts.user.key();
+        activity.enabled = enabled;
+        Ok(())
+    }
+
+    /// Sets or, with `None`, clears the key that may report commit statuses
+    /// besides collaborators, e.g. a CI service. Only its statuses satisfy
+    /// required checks.
+    pub fn set_status_key(ctx: Context<ModifyRepo>, status_key: Option<Pubkey>) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        repo.status_key = status_key;
+        repo.touch()?;
+        grow_repo_to_fit(repo, &ctx.accounts.owner, &ctx.accounts.system_program)
+    }
+
+    /// Records the outcome of a check, identified by `context`, on a
+    /// commit. Each context has its own status, so `build` and `test` can
+    /// be reported independently.
+    pub fn set_commit_status(
+        ctx: Context<SetCommitStatus>,
+        commit_hash: String,
+        context: String,
+        state: StatusState,
+        description: String,
+    ) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        let signer_key = ctx.accounts.signer.key();
+        if repo.status_key != Some(signer_key) {
+            repo.ensure_collaborator(&signer_key)?;
+        }
+        require!(
+            repo.object_format.is_valid_hash(&commit_hash),
+            GitError::InvalidCommitHash
+        );
+        require!(
+            !context.is_empty()
+                && context.len() <= MAX_STATUS_CONTEXT_LEN
+                && description.len() <= MAX_STATUS_DESCRIPTION_LEN,
+            GitError::InvalidCommitStatus
+        );
+        let status = &mut ctx.accounts.status;
+        status.repo = repo.key();
+        status.commit_hash = commit_hash;
+        status.context = context;
+        status.state = state;
+        status.description = description;
+        status.updated_by = signer_key;
+        status.updated_at = Clock::get()?.unix_timestamp;
+        if repo.emits(EVENT_LEVEL_ALL) {
+            emit!(CommitStatusEvent {
+                repo: status.repo,
+                seq: repo.next_event_seq(),
+                commit_hash: status.commit_hash.clone(),
+                context: status.context.clone(),
+                state,
+                signer: signer_key,
+            });
+        }
+        repo.touch()
+    }
+
+    /// Declares that this repository depends on `dependency.repo` at
+    /// `dependency.pinned_commit`, replacing any earlier pin on the same
+    /// repository. The pin may use either object format.
+    pub fn add_dependency(ctx: Context<ModifyRepo>, dependency: Dependency) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        require!(dependency.repo.is_valid(), GitError::InvalidRepoName);
+        require!(
+            ObjectFormat::Sha1.is_valid_hash(&dependency.pinned_commit)
+                || ObjectFormat::Sha256.is_valid_hash(&dependency.pinned_commit),
+            GitError::InvalidCommitHash
+        );
+        match repo.dependencies.iter_mut().find(|d| d.repo == dependency.repo) {
+            Some(existing) => existing.pinned_commit = dependency.pinned_commit,
+            None => {
+                require!(
+                    repo.dependencies.len() < MAX_DEPENDENCIES,
+                    GitError::TooManyDependencies
+                );
+                repo.dependencies.push(dependency);
+            }
+        }
+        repo.touch()?;
+        grow_repo_to_fit(repo, &ctx.accounts.owner, &ctx.accounts.system_program)
+    }
+
+    /// Removes the dependency on `dependency`.
+    pub fn remove_dependency(ctx: Context<ModifyRepo>, dependency: RepoId) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        repo.dependencies.retain(|d| d.repo != dependency);
+        repo.touch()?;
+        Ok(())
+    }
+
+    /// Checks the collaborator list for the invariants the program relies
+    /// on, e.g. after a migration: the owner listed exactly once and no
+    /// duplicates. Returns what it found rather than failing, so operators
+    /// can see what needs repair. A repository over the collaborator limit
+    /// fails `is_well_formed` and is rejected with `CorruptState` instead.
+    pub fn verify_collaborators(ctx: Context<ReadRepo>) -> Result<CollaboratorReport> {
+        let repo = &ctx.accounts.repo;
+        let owner_entries = repo
+            .collaborators
+            .iter()
+            .filter(|key| **key == repo.owner)
+            .count();
+        let mut sorted = repo.collaborators.clone();
+        sorted.sort();
+        let duplicates = sorted
+            .windows(2)
+            .filter(|pair| pair[0] == pair[1] && pair[0] != repo.owner)
+            .count();
+        let count = repo.collaborators.len();
+        Ok(CollaboratorReport {
+            well_formed: owner_entries == 1 && duplicates == 0 && count <= MAX_COLLABORATORS,
+            owner_entries: owner_entries as u32,
+            duplicates: duplicates as u32,
+            count: count as u32,
+        })
+    }
+
+    /// Records the signer's consent for `service` to aggregate their public
+    /// activity, limited to `scopes` (a bitmask interpreted off-chain) until
+    /// `expires_at`. Granting again replaces the previous grant. Relayers
+    /// check the grant; the program only records it.
+    pub fn grant_read(ctx: Context<GrantRead>, scopes: u32, expires_at: i64) -> Result<()> {
+        require!(
+            expires_at > Clock::get()?.unix_timestamp,
+            GitError::InvalidGrantExpiry
+        );
+        let grant = &mut ctx.accounts.grant;
+        grant.user = ctx.accounts.user.key();
+        grant.service = ctx.accounts.service.key();
+        grant.scopes = scopes;
+        grant.expires_at = expires_at;
+        emit!(ReadGrantedEvent {
+            user: grant.user,
+            service: grant.service,
+            scopes,
+            expires_at,
+        });
+        Ok(())
+    }
+
+    /// Withdraws a read grant and reclaims its rent.
+    pub fn revoke_read(ctx: Context<RevokeRead>) -> Result<()> {
+        emit!(ReadRevokedEvent {
+            user: ctx.accounts.user.key(),
+            service: ctx.accounts.grant.service,
+        });
+        Ok(())
+    }
+
+    /// Starts a recurring sponsorship of the repository. The first
+    /// `amount_per_period` lamports go to the owner right away; another
+    /// `prefund_periods` payments are escrowed in the sponsorship account
+    /// for `claim_sponsorship`. Sponsors top up the escrow with plain
+    /// transfers to that account.
+    pub fn sponsor_repository(
+        ctx: Context<SponsorRepository>,
+        amount_per_period: u64,
+        period_seconds: u32,
+        prefund_periods: u32,
+    ) -> Result<()> {
+        require!(
+            amount_per_period > 0 && period_seconds > 0,
+            GitError::InvalidSponsorship
+        );
+        let escrow = amount_per_period
+            .checked_mul(prefund_periods as u64)
+            .ok_or(GitError::InvalidSponsorship)?;
+        let sponsor = ctx.accounts.sponsor.to_account_info();
+        for (to, amount) in [
+            (ctx.accounts.owner.to_account_info(), amount_per_period),
+            (ctx.accounts.sponsorship.to_account_info(), escrow),
+        ] {
+            if amount > 0 {
+                system_program::transfer(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        system_program::Transfer {
+                            from: sponsor.clone(),
+                            to,
+                        },
+                    ),
+                    amount,
+                )?;
+            }
+        }
+        let sponsorship = &mut ctx.accounts.sponsorship;
+        sponsorship.sponsor = sponsor.key();
+        sponsorship.repo = ctx.accounts.repo.key();
+        sponsorship.amount_per_period = amount_per_period;
+        sponsorship.period_seconds = period_seconds;
+        sponsorship.last_paid = Clock::get()?.unix_timestamp;
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_not_redirected()?;
+        // No `touch`: sponsoring is not activity by the repository's
+        // maintainers and must not hold off auto-archiving.
+        if repo.emits(EVENT_LEVEL_ALL) {
+            emit!(SponsorshipEvent {
+                repo: sponsorship.repo,
+                seq: repo.next_event_seq(),
+                sponsor: sponsorship.sponsor,
+                amount: amount_per_period,
+                paid_at: sponsorship.last_paid,
+            });
+        }
+        Ok(())
+    }
+
+    /// Pays the owner one period's amount from the sponsorship escrow once
+    /// that period has elapsed. Missed periods can be claimed one call at
+    /// a time.
+    pub fn claim_sponsorship(ctx: Context<ClaimSponsorship>) -> Result<()> {
+        ctx.accounts.repo.ensure_owner(ctx.accounts.owner.key)?;
+        let sponsorship = &mut ctx.accounts.sponsorship;
+        let due_at = sponsorship
+            .last_paid
+            .saturating_add(sponsorship.period_seconds as i64);
+        require!(
+            Clock::get()?.unix_timestamp >= due_at,
+            GitError::SponsorshipNotDue
+        );
+        let amount = sponsorship.amount_per_period;
+        let escrow = sponsorship.to_account_info();
+        let rent = Rent::get()?.minimum_balance(escrow.data_len());
+        require!(
+            escrow.lamports().saturating_sub(rent) >= amount,
+            GitError::SponsorshipUnderfunded
+        );
+        **escrow.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.owner.to_account_info().try_borrow_mut_lamports()? += amount;
+        sponsorship.last_paid = due_at;
+        let repo = &mut ctx.accounts.repo;
+        if repo.emits(EVENT_LEVEL_ALL) {
+            emit!(SponsorshipEvent {
+                repo: sponsorship.repo,
+                seq: repo.next_event_seq(),
+                sponsor: sponsorship.sponsor,
+                amount,
+                paid_at: due_at,
+            });
+        }
+        repo.touch()
+    }
+
+    /// Ends a sponsorship. Periods that have already elapsed are owed to
+    /// the repository owner and paid out first, as far as the escrow
+    /// covers them; the rest of the escrow and the account's rent return to
+    /// the sponsor. If the repository was closed, everything returns to
+    /// the sponsor.
+    pub fn end_sponsorship(ctx: Context<EndSponsorship>) -> Result<()> {
+        let repo_info = ctx.accounts.repo.to_account_info();
+        let repo = (repo_info.owner == ctx.program_id)
+            .then(|| {
+                let data = repo_info.try_borrow_data().ok()?;
+                Repository::try_deserialize(&mut &data[..]).ok()
+            })
+            .flatten();
+        let Some(mut repo) = repo else {
+            return Ok(());
+        };
+        require_keys_eq!(
+            ctx.accounts.owner.key(),
+            repo.owner,
+            GitError::Unauthorized
+        );
+        let sponsorship = &mut ctx.accounts.sponsorship;
+        let now = Clock::get()?.unix_timestamp;
+        let periods = now.saturating_sub(sponsorship.last_paid) / sponsorship.period_seconds as i64;
+        let escrow = sponsorship.to_account_info();
+        let rent = Rent::get()?.minimum_balance(escrow.data_len());
+        let amount = (periods.max(0) as u64)
+            .saturating_mul(sponsorship.amount_per_period)
+            .min(escrow.lamports().saturating_sub(rent));
+        if amount == 0 {
+            return Ok(());
+        }
+        **escrow.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.owner.try_borrow_mut_lamports()? += amount;
+        if repo.emits(EVENT_LEVEL_ALL) {
+            emit!(SponsorshipEvent {
+                repo: repo_info.key(),
+                seq: repo.next_event_seq(),
+                sponsor: sponsorship.sponsor,
+                amount,
+                paid_at: now,
+            });
+            repo.try_serialize(&mut &mut repo_info.try_borrow_mut_data()?[..])?;
+        }
+        Ok(())
+    }
+
+    /// Attests that the Arweave transactions behind a branch tip are
+    /// permanently stored. The program trusts the collaborator's check;
+    /// clients should only clone from confirmed tips.
+    pub fn confirm_arweave(ctx: Context<UpdateBranch>, branch_name: String) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        let signer_key = *ctx.accounts.signer.key;
+        repo.ensure_collaborator(&signer_key)?;
+        repo.ensure_not_redirected()?;
+        let branch = repo
+            .branches
+            .iter_mut()
+            .find(|b| b.name == branch_name)
+            .ok_or(GitError::BranchNotFound)?;
+        require!(!branch.is_symbolic(), GitError::SymbolicRefNotWritable);
+        require!(
+            branch.commit.storage_kind == StorageKind::Arweave,
+            GitError::NotArweaveStorage
+        );
+        branch.commit.confirmed = true;
+        let commit_hash = branch.commit.commit_hash.clone();
+        repo.touch()?;
+        if repo.emits(EVENT_LEVEL_BRANCHES) {
+            emit!(ArweaveConfirmedEvent {
+                repo: repo.key(),
+                seq: repo.next_event_seq(),
+                branch: branch_name,
+                commit_hash,
+                signer: signer_key,
+            });
+        }
+        Ok(())
+    }
+
+    /// Creates a shareable invite that lets one signer add themselves as a
+    /// collaborator until `expires_at`, if set. The link carries a random
+    /// secret and only its SHA-256, `secret_hash`, goes on-chain, so the
+    /// unused invite cannot be claimed by reading chain state. Claiming
+    /// publishes the secret, which is why every invite is single-use.
+    pub fn create_invite_link(
+        ctx: Context<CreateInviteLink>,
+        secret_hash: [u8; 32],
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        ctx.accounts.repo.ensure_owner(ctx.accounts.owner.key)?;
+        ctx.accounts.repo.ensure_not_redirected()?;
+        if let Some(expires_at) = expires_at {
+            require!(
+                expires_at > Clock::get()?.unix_timestamp,
+                GitError::InvalidInvite
+            );
+        }
+        let invite = &mut ctx.accounts.invite;
+        invite.repo = ctx.accounts.repo.key();
+        invite.secret_hash = secret_hash;
+        invite.expires_at = expires_at;
+        ctx.accounts.repo.touch()
+    }
+
+    /// Adds the signer as a collaborator using the invite whose
+    /// `secret_hash` is the SHA-256 of the link's secret; the accounts
+    /// check the match. The invite is closed to the owner by any claim,
+    /// including one by an existing collaborator, since the secret is
+    /// public from then on.
+    pub fn claim_invite_link(ctx: Context<ClaimInviteLink>, _secret: [u8; 32]) -> Result<()> {
+        if let Some(expires_at) = ctx.accounts.invite.expires_at {
+            require!(
+                Clock::get()?.unix_timestamp < expires_at,
+                GitError::InviteExpired
+            );
+        }
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_not_redirected()?;
+        let claimer = ctx.accounts.claimer.key();
+        if repo.is_owner(&claimer) || repo.collaborators.contains(&claimer) {
+            return Ok(());
+        }
+        require!(
+            repo.collaborators.len() < MAX_COLLABORATORS,
+            GitError::TooManyCollaborators
+        );
+        repo.collaborators.push(claimer);
+        repo.touch()?;
+        grow_repo_to_fit(repo, &ctx.accounts.claimer, &ctx.accounts.system_program)
+    }
+
+    /// Deletes an unclaimed invite and reclaims its rent.
+    pub fn revoke_invite_link(ctx: Context<RevokeInviteLink>) -> Result<()> {
+        ctx.accounts.repo.ensure_owner(ctx.accounts.owner.key)?;
+        ctx.accounts.repo.touch()
+    }
+
+    /// Sets or, with `None`, clears the relayer key allowed to report
+    /// clones observed at its gateway.
+    pub fn set_clone_relayer(ctx: Context<ModifyRepo>, relayer: Option<Pubkey>) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        repo.clone_relayer = relayer;
+        repo.touch()?;
+        grow_repo_to_fit(repo, &ctx.accounts.owner, &ctx.accounts.system_program)
+    }
+
+    /// Adds `clones` observed clones to `clone_count`. Reports are capped at
+    /// `MAX_CLONES_PER_REPORT` and spaced at least `CLONE_REPORT_INTERVAL`
+    /// apart, bounding how fast a relayer can inflate the count. Clones do
+    /// not count as repository activity.
+    pub fn record_clone(ctx: Context<RecordClone>, clones: u32) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        require!(
+            repo.clone_relayer == Some(ctx.accounts.relayer.key()),
+            GitError::Unauthorized
+        );
+        require!(
+            (1..=MAX_CLONES_PER_REPORT).contains(&clones),
+            GitError::InvalidCloneReport
+        );
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now >= repo.last_clone_report.saturating_add(CLONE_REPORT_INTERVAL),
+            GitError::CloneReportTooSoon
+        );
+        repo.clone_count = repo.clone_count.saturating_add(clones as u64);
+        repo.last_clone_report = now;
+        if repo.emits(EVENT_LEVEL_ALL) {
+            emit!(CloneRecordedEvent {
+                repo: repo.key(),
+                seq: repo.next_event_seq(),
+                clones,
+                clone_count: repo.clone_count,
+            });
+        }
+        Ok(())
+    }
+
+    /// Replaces the status contexts that must report success on a commit
+    /// before it can become the branch's tip, by any instruction that moves
+    /// it. An empty list removes the requirement; until then the branch is
+    /// protected from bulk deletes. Only statuses from `status_key` satisfy
+    /// the checks, so one must be set first.
+    pub fn set_required_checks(
+        ctx: Context<ModifyRepo>,
+        branch_name: String,
+        checks: Vec<String>,
+    ) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        require!(
+            checks.len() <= MAX_REQUIRED_CHECKS
+                && checks
+                    .iter()
+                    .all(|c| !c.is_empty() && c.len() <= MAX_STATUS_CONTEXT_LEN),
+            GitError::InvalidRequiredChecks
+        );
+        require!(
+            checks.is_empty() || repo.status_key.is_some(),
+            GitError::StatusKeyNotSet
+        );
+        let branch = repo
+            .branches
+            .iter_mut()
+            .find(|b| b.name == branch_name)
+            .ok_or(GitError::BranchNotFound)?;
+        require!(!branch.is_symbolic(), GitError::SymbolicRefNotWritable);
+        branch.required_checks = checks;
+        repo.touch()?;
+        grow_repo_to_fit(repo, &ctx.accounts.owner, &ctx.accounts.system_program)
+    }
+
+    /// Replaces the core git settings clients apply to working trees.
+    pub fn set_git_config(ctx: Context<ModifyRepo>, git_config: GitConfig) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        repo.ensure_not_redirected()?;
+        repo.git_config = git_config;
+        repo.touch()?;
+        grow_repo_to_fit(repo, &ctx.accounts.owner, &ctx.accounts.system_program)
+    }
+
+    /// Points a branch at built artifacts, e.g. a compiled release, stored
+    /// on Arweave separately from its source. `None` clears the pointer.
+    pub fn set_branch_artifact(
+        ctx: Context<UpdateBranch>,
+        branch_name: String,
+        artifact_arweave_tx: Option<String>,
+    ) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        let signer_key = *ctx.accounts.signer.key;
+        repo.ensure_collaborator(&signer_key)?;
+        repo.ensure_not_redirected()?;
+        if let Some(tx) = &artifact_arweave_tx {
+            require!(
+                StorageKind::Arweave.is_valid_id(tx),
+                GitError::InvalidStorageId
+            );
+        }
+        let branch = repo
+            .branches
+            .iter_mut()
+            .find(|b| b.name == branch_name)
+            .ok_or(GitError::BranchNotFound)?;
+        require!(!branch.is_symbolic(), GitError::SymbolicRefNotWritable);
+        branch.artifact_arweave_tx = artifact_arweave_tx;
+        repo.touch()?;
+        grow_repo_to_fit(repo, &ctx.accounts.signer, &ctx.accounts.system_program)
+    }
+
+    /// Records that an owner key signed just now. `close_repo`,
+    /// `rotate_owner_key` and `execute_destructive_action` each consume a
+    /// proof made by their own signer within the last
+    /// `OWNER_PROOF_MAX_AGE_SLOTS` slots, so clients call this first,
+    /// typically in the same transaction. `nonce` is the client's
+    /// challenge, kept so it can match the proof.
+    pub fn prove_owner_control(ctx: Context<ModifyRepo>, nonce: [u8; 32]) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        repo.owner_proof = Some(OwnerProof {
+            nonce,
+            slot: Clock::get()?.slot,
+            prover: ctx.accounts.owner.key(),
+        });
+        repo.touch()?;
+        grow_repo_to_fit(repo, &ctx.accounts.owner, &ctx.accounts.system_program)
+    }
+
+    /// Sets or, with `None`, disables the inactivity period after which the
+    /// repository rejects mutating instructions.
+    pub fn set_auto_archive(ctx: Context<ModifyRepo>, after: Option<i64>) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        if let Some(after) = after {
+            require!(after > 0, GitError::InvalidThreshold);
+        }
+        repo.auto_archive_after = after;
+        repo.touch()?;
+        grow_repo_to_fit(repo, &ctx.accounts.owner, &ctx.accounts.system_program)
+    }
+
+    /// Reactivates a repository archived for inactivity by resetting its
+    /// activity clock.
+    pub fn touch_repo(ctx: Context<ModifyRepo>) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        repo.repo_last_activity = Clock::get()?.unix_timestamp;
+        repo.touch()
+    }
+
+    /// Marks a branch as a work-in-progress draft, or clears the mark.
+    pub fn set_branch_draft(
+        ctx: Context<UpdateBranch>,
+        branch_name: String,
+        draft: bool,
+    ) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_collaborator(ctx.accounts.signer.key)?;
+        repo.ensure_not_redirected()?;
+        let branch = repo
+            .branches
+            .iter_mut()
+            .find(|b| b.name == branch_name)
+            .ok_or(GitError::BranchNotFound)?;
+        require!(!branch.is_symbolic(), GitError::SymbolicRefNotWritable);
+        branch.draft = draft;
+        repo.touch()
+    }
+
+    /// Gives `co_owner` the same authority as the owner. Any owner or
+    /// co-owner may add one.
+    pub fn add_co_owner(ctx: Context<ModifyRepo>, co_owner: Pubkey) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        require!(!repo.is_owner(&co_owner), GitError::InvalidCoOwner);
+        require!(
+            repo.co_owners.len() < MAX_CO_OWNERS,
+            GitError::TooManyCoOwners
+        );
+        repo.co_owners.push(co_owner);
+        repo.touch()?;
+        // Emitted regardless of the event level, like other changes to who
+        // holds owner authority.
+        emit!(CoOwnerAddedEvent {
+            repo: repo.key(),
+            seq: repo.next_event_seq(),
+            co_owner,
+            signer: ctx.accounts.owner.key(),
+        });
+        grow_repo_to_fit(repo, &ctx.accounts.owner, &ctx.accounts.system_program)
+    }
+
+    /// Revokes a co-owner's authority. Any owner or co-owner may remove any
+    /// co-owner, themselves included; `owner` itself cannot be removed.
+    pub fn remove_co_owner(ctx: Context<ModifyRepo>, co_owner: Pubkey) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        repo.ensure_owner(ctx.accounts.owner.key)?;
+        let index = repo
+            .co_owners
+            .iter()
+            .position(|key| *key == co_owner)
+            .ok_or(GitError::InvalidCoOwner)?;
+        repo.co_owners.remove(index);
+        repo.touch()?;
+        emit!(CoOwnerRemovedEvent {
+            repo: repo.key(),
+            seq: repo.next_event_seq(),
+            co_owner,
+            signer: ctx.accounts.owner.key(),
+        });
+        Ok(())
+    }
+
+    /// Opts the signer's repositories created or renamed from now on in to,
+    /// or out of, case-insensitive name uniqueness. While opted in,
+    /// `create_repo`, `begin_repo` and renames must claim a name record.
+    pub fn set_name_policy(ctx: Context<SetNamePolicy>, case_insensitive: bool) -> Result<()> {
+        let policy = &mut ctx.accounts.name_policy;
+        policy.owner = ctx.accounts.owner.key();
+        policy.case_insensitive = case_insensitive;
+        Ok(())
+    }
+
+    /// Removes every branch whose name starts with `prefix`. The default
+    /// branch, symbolic refs, any branch a symbolic ref points at and
+    /// protected branches are kept.
+    pub fn delete_branches_by_prefix(ctx: Context<UpdateBranch>, prefix: String) -> Result<()> {
+        let repo = &mut ctx.accounts.repo;
+        let signer_key = *ctx.accounts.signer.key;
+        repo.ensure_collaborator(&signer_key)?;
+        repo.ensure_not_redirected()?;
+        require!(!prefix.is_empty(), GitError::EmptyBranchPrefix);
+        let deleted: Vec<String> = repo
+            .branches
+            .iter()
+            .filter(|b| !b.is_symbolic() && !b.is_protected() && b.name.starts_with(&prefix))
+            .filter(|b| !repo.is_ref_target(&b.name))
+            .map(|b| b.name.clone())
+            .collect();
+        repo.remove_branches(&deleted);
+        repo.touch()?;
+        if repo.emits(EVENT_LEVEL_BRANCHES) {
+            emit!(BranchesDeletedEvent {
+                repo: repo.key(),
+                seq: repo.next_event_seq(),
+                prefix,
+                count: deleted.len() as u16,
+                names: deleted,
+                signer: signer_key,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct CreateRepo<'info> {
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer = signer,
+        space = config.default_repo_space as usize,
+        seeds = [b"repository", signer.key().as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub repo: Account<'info, Repository>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + RepoNameRecord::INIT_SPACE,
+        seeds = [b"repo_name", signer.key().as_ref(), name.to_lowercase().as_bytes()],
+        bump
+    )]
+    pub name_record: Option<Account<'info, RepoNameRecord>>,
+    /// CHECK: The signer's `NamePolicy`, which need not exist; read by
+    /// `case_insensitive_names`.
+    #[account(seeds = [b"name_policy", signer.key().as_ref()], bump)]
+    pub name_policy: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// The signer's activity feed, if they keep one.
+    #[account(mut, seeds = [b"activity", signer.key().as_ref()], bump)]
+    pub activity: Option<Account<'info, UserActivity>>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String, space: u32)]
+pub struct BeginRepo<'info> {
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, Config>,
+    #[account(
+        init,
+        payer = signer,
+        space = space as usize,
+        seeds = [b"repository", signer.key().as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub repo: Account<'info, Repository>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + RepoNameRecord::INIT_SPACE,
+        seeds = [b"repo_name", signer.key().as_ref(), name.to_lowercase().as_bytes()],
+        bump
+    )]
+    pub name_record: Option<Account<'info, RepoNameRecord>>,
+    /// CHECK: The signer's `NamePolicy`, which need not exist; read by
+    /// `case_insensitive_names`.
+    #[account(seeds = [b"name_policy", signer.key().as_ref()], bump)]
+    pub name_policy: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ModifyRepo<'info> {
+    #[account(
+        mut,
+        constraint = repo.is_well_formed() @ GitError::CorruptState,
+        constraint = repo.is_owner(owner.key) @ GitError::Unauthorized
+    )]
+    pub repo: Account<'info, Repository>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateBranch<'info> {
+    #[account(mut, constraint = repo.is_well_formed() @ GitError::CorruptState)]
+    pub repo: Account<'info, Repository>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// The signer's token account for the repository's `gate_mint`, needed
+    /// to move a branch tip while a stake gate is set.
+    pub gate_token: Option<Account<'info, TokenAccount>>,
+    /// The repository's linter, co-signing a `ConventionProof`.
+    pub linter: Option<Signer<'info>>,
+    /// The signer's activity feed, if they keep one.
+    #[account(mut, seeds = [b"activity", signer.key().as_ref()], bump)]
+    pub activity: Option<Account<'info, UserActivity>>,
+}
+
+#[derive(Accounts)]
+pub struct MergeBranch<'info> {
+    #[account(mut, constraint = repo.is_well_formed() @ GitError::CorruptState)]
+    pub repo: Account<'info, Repository>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// The signer's token account for the repository's `gate_mint`, needed
+    /// to move a branch tip while a stake gate is set.
+    pub gate_token: Option<Account<'info, TokenAccount>>,
+    /// The signer's activity feed, if they keep one.
+    #[account(mut, seeds = [b"activity", signer.key().as_ref()], bump)]
+    pub activity: Option<Account<'info, UserActivity>>,
+    /// CHECK: The repository's `CodeOwners`, which need not exist; read by
+    /// `load_code_owners`.
+    #[account(seeds = [b"code_owners", repo.key().as_ref()], bump)]
+    pub code_owners: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(new_name: Option<String>)]
+pub struct UpdateRepo<'info> {
+    #[account(
+        mut,
+        constraint = repo.is_well_formed() @ GitError::CorruptState,
+        constraint = repo.is_owner(owner.key) @ GitError::Unauthorized
+    )]
+    pub repo: Account<'info, Repository>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// The name record of the current name, released on rename.
+    #[account(
+        mut,
+        constraint = name_record.repo == repo.key() @ GitError::InvalidNameRecord
+    )]
+    pub name_record: Option<Account<'info, RepoNameRecord>>,
+    /// The name record claiming `new_name`.
+    #[account(
+        init_if_needed,
+        constraint = new_name.is_some() @ GitError::InvalidNameRecord,
+        payer = owner,
+        space = 8 + RepoNameRecord::INIT_SPACE,
+        seeds = [
+            b"repo_name",
+            repo.owner.as_ref(),
+            new_name.as_deref().unwrap_or_default().to_lowercase().as_bytes()
+        ],
+        bump
+    )]
+    pub new_name_record: Option<Account<'info, RepoNameRecord>>,
+    /// CHECK: The repository owner's `NamePolicy`, which need not exist.
+    #[account(seeds = [b"name_policy", repo.owner.as_ref()], bump)]
+    pub name_policy: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRepo<'info> {
+    #[account(
+        mut,
+        constraint = repo.is_owner(owner.key) @ GitError::Unauthorized,
+        close = payee,
+        constraint = repo.is_well_formed() @ GitError::CorruptState
+    )]
+    pub repo: Account<'info, Repository>,
+    #[account(
+        mut,
+        close = payee,
+        constraint = name_record.repo == repo.key() @ GitError::InvalidNameRecord
+    )]
+    pub name_record: Option<Account<'info, RepoNameRecord>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// CHECK: The repository's `owner`, who receives the refund.
+    #[account(mut, address = repo.owner @ GitError::Unauthorized)]
+    pub payee: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ShrinkRepo<'info> {
+    #[account(
+        mut,
+        constraint = repo.is_well_formed() @ GitError::CorruptState,
+        constraint = repo.is_owner(owner.key) @ GitError::Unauthorized
+    )]
+    pub repo: Account<'info, Repository>,
+    pub owner: Signer<'info>,
+    /// CHECK: The repository's `owner`, who receives the freed rent.
+    #[account(mut, address = repo.owner @ GitError::Unauthorized)]
+    pub payee: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RotateOwnerKey<'info> {
+    #[account(
+        mut,
+        constraint = repo.is_well_formed() @ GitError::CorruptState,
+        constraint = repo.is_owner(owner.key) @ GitError::Unauthorized
+    )]
+    pub repo: Account<'info, Repository>,
+    pub owner: Signer<'info>,
+    pub new_owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RequestDestructiveAction<'info> {
+    #[account(
+        mut,
+        constraint = repo.is_well_formed() @ GitError::CorruptState,
+        constraint = repo.is_owner(owner.key) @ GitError::Unauthorized
+    )]
+    pub repo: Account<'info, Repository>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + PendingAction::INIT_SPACE,
+        seeds = [b"pending_action", repo.key().as_ref()],
+        bump
+    )]
+    pub pending: Account<'info, PendingAction>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub new_owner: Option<Signer<'info>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteDestructiveAction<'info> {
+    #[account(
+        mut,
+        constraint = repo.is_well_formed() @ GitError::CorruptState,
+        constraint = repo.is_owner(owner.key) @ GitError::Unauthorized
+    )]
+    pub repo: Account<'info, Repository>,
+    #[account(
+        mut,
+        close = owner,
+        has_one = repo,
+        seeds = [b"pending_action", repo.key().as_ref()],
+        bump
+    )]
+    pub pending: Account<'info, PendingAction>,
+    /// Needed to close a repository that holds a name record.
+    #[account(
+        mut,
+        constraint = name_record.repo == repo.key() @ GitError::InvalidNameRecord
+    )]
+    pub name_record: Option<Account<'info, RepoNameRecord>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// CHECK: The repository's `owner`, who receives the refund of a
+    /// scheduled close.
+    #[account(mut, address = repo.owner @ GitError::Unauthorized)]
+    pub payee: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelDestructiveAction<'info> {
+    #[account(
+        mut,
+        constraint = repo.is_well_formed() @ GitError::CorruptState,
+        constraint = repo.is_owner(owner.key) @ GitError::Unauthorized
+    )]
+    pub repo: Account<'info, Repository>,
+    #[account(
+        mut,
+        close = owner,
+        has_one = repo,
+        seeds = [b"pending_action", repo.key().as_ref()],
+        bump
+    )]
+    pub pending: Account<'info, PendingAction>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BeginCollaboratorImport<'info> {
+    #[account(
+        mut,
+        constraint = repo.is_well_formed() @ GitError::CorruptState,
+        constraint = repo.is_owner(owner.key) @ GitError::Unauthorized
+    )]
+    pub repo: Account<'info, Repository>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + CollaboratorImport::INIT_SPACE,
+        seeds = [b"collaborator_import", repo.key().as_ref()],
+        bump
+    )]
+    pub import: Account<'info, CollaboratorImport>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AppendCollaborators<'info> {
+    #[account(
+        mut,
+        constraint = repo.is_well_formed() @ GitError::CorruptState,
+        constraint = repo.is_owner(owner.key) @ GitError::Unauthorized
+    )]
+    pub repo: Account<'info, Repository>,
+    #[account(
+        mut,
+        has_one = repo,
+        seeds = [b"collaborator_import", repo.key().as_ref()],
+        bump
+    )]
+    pub import: Account<'info, CollaboratorImport>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FinishCollaboratorImport<'info> {
+    #[account(
+        mut,
+        constraint = repo.is_well_formed() @ GitError::CorruptState,
+        constraint = repo.is_owner(owner.key) @ GitError::Unauthorized
+    )]
+    pub repo: Account<'info, Repository>,
+    #[account(
+        mut,
+        close = owner,
+        has_one = repo,
+        seeds = [b"collaborator_import", repo.key().as_ref()],
+        bump
+    )]
+    pub import: Account<'info, CollaboratorImport>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReadRepo<'info> {
+    #[account(constraint = repo.is_well_formed() @ GitError::CorruptState)]
+    pub repo: Account<'info, Repository>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterNotification<'info> {
+    #[account(
+        mut,
+        constraint = repo.is_well_formed() @ GitError::CorruptState,
+        constraint = repo.is_owner(owner.key) @ GitError::Unauthorized
+    )]
+    pub repo: Account<'info, Repository>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + NotificationTarget::INIT_SPACE,
+        seeds = [b"notification", repo.key().as_ref()],
+        bump
+    )]
+    pub target: Account<'info, NotificationTarget>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnregisterNotification<'info> {
+    #[account(
+        mut,
+        constraint = repo.is_well_formed() @ GitError::CorruptState,
+        constraint = repo.is_owner(owner.key) @ GitError::Unauthorized
+    )]
+    pub repo: Account<'info, Repository>,
+    #[account(
+        mut,
+        close = owner,
+        has_one = repo,
+        seeds = [b"notification", repo.key().as_ref()],
+        bump
+    )]
+    pub target: Account<'info, NotificationTarget>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetCodeOwners<'info> {
+    #[account(
+        mut,
+        constraint = repo.is_well_formed() @ GitError::CorruptState,
+        constraint = repo.is_owner(owner.key) @ GitError::Unauthorized
+    )]
+    pub repo: Account<'info, Repository>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + CodeOwners::INIT_SPACE,
+        seeds = [b"code_owners", repo.key().as_ref()],
+        bump
+    )]
+    pub code_owners: Account<'info, CodeOwners>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CheckCodeOwners<'info> {
+    #[account(constraint = repo.is_well_formed() @ GitError::CorruptState)]
+    pub repo: Account<'info, Repository>,
+    #[account(
+        has_one = repo,
+        seeds = [b"code_owners", repo.key().as_ref()],
+        bump
+    )]
+    pub code_owners: Account<'info, CodeOwners>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::GitSolana>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ GitError::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(mut, seeds = [b"config"], bump, has_one = admin @ GitError::Unauthorized)]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(commit_hash: String, context: String)]
+pub struct SetCommitStatus<'info> {
+    #[account(mut, constraint = repo.is_well_formed() @ GitError::CorruptState)]
+    pub repo: Account<'info, Repository>,
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = 8 + CommitStatus::INIT_SPACE,
+        seeds = [
+            b"status",
+            repo.key().as_ref(),
+            &commit_hash_seed(&commit_hash),
+            context.as_bytes()
+        ],
+        bump
+    )]
+    pub status: Account<'info, CommitStatus>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GrantRead<'info> {
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + ReadGrant::INIT_SPACE,
+        seeds = [b"read_grant", user.key().as_ref(), service.key().as_ref()],
+        bump
+    )]
+    pub grant: Account<'info, ReadGrant>,
+    /// CHECK: Only the key is recorded; the service does not sign.
+    pub service: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeRead<'info> {
+    #[account(
+        mut,
+        close = user,
+        has_one = user,
+        seeds = [b"read_grant", user.key().as_ref(), grant.service.as_ref()],
+        bump
+    )]
+    pub grant: Account<'info, ReadGrant>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SponsorRepository<'info> {
+    #[account(mut, has_one = owner, constraint = repo.is_well_formed() @ GitError::CorruptState)]
+    pub repo: Account<'info, Repository>,
+    /// CHECK: The repository owner, receiving the first payment.
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = sponsor,
+        space = 8 + Sponsorship::INIT_SPACE,
+        seeds = [b"sponsorship", sponsor.key().as_ref(), repo.key().as_ref()],
+        bump
+    )]
+    pub sponsorship: Account<'info, Sponsorship>,
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimSponsorship<'info> {
+    #[account(mut, has_one = owner, constraint = repo.is_well_formed() @ GitError::CorruptState)]
+    pub repo: Account<'info, Repository>,
+    #[account(
+        mut,
+        has_one = repo,
+        seeds = [b"sponsorship", sponsorship.sponsor.as_ref(), repo.key().as_ref()],
+        bump
+    )]
+    pub sponsorship: Account<'info, Sponsorship>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct EndSponsorship<'info> {
+    #[account(
+        mut,
+        close = sponsor,
+        has_one = sponsor,
+        seeds = [b"sponsorship", sponsor.key().as_ref(), sponsorship.repo.as_ref()],
+        bump
+    )]
+    pub sponsorship: Account<'info, Sponsorship>,
+    /// CHECK: The sponsored repository; read by `end_sponsorship` unless
+    /// it was closed.
+    #[account(mut, address = sponsorship.repo)]
+    pub repo: UncheckedAccount<'info>,
+    /// CHECK: The repository owner, paid the elapsed periods; checked
+    /// against the repository by `end_sponsorship`.
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(secret_hash: [u8; 32])]
+pub struct CreateInviteLink<'info> {
+    #[account(
+        mut,
+        constraint = repo.is_well_formed() @ GitError::CorruptState,
+        constraint = repo.is_owner(owner.key) @ GitError::Unauthorized
+    )]
+    pub repo: Account<'info, Repository>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Invite::INIT_SPACE,
+        seeds = [b"invite", repo.key().as_ref(), secret_hash.as_ref()],
+        bump
+    )]
+    pub invite: Account<'info, Invite>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(secret: [u8; 32])]
+pub struct ClaimInviteLink<'info> {
+    #[account(mut, constraint = repo.is_well_formed() @ GitError::CorruptState)]
+    pub repo: Account<'info, Repository>,
+    #[account(
+        mut,
+        has_one = repo,
+        close = owner,
+        seeds = [b"invite", repo.key().as_ref(), hash(&secret).as_ref()],
+        bump
+    )]
+    pub invite: Account<'info, Invite>,
+    /// CHECK: The repository's `owner`, refunded the invite's rent.
+    #[account(mut, address = repo.owner @ GitError::Unauthorized)]
+    pub owner: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub claimer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeInviteLink<'info> {
+    #[account(
+        mut,
+        constraint = repo.is_well_formed() @ GitError::CorruptState,
+        constraint = repo.is_owner(owner.key) @ GitError::Unauthorized
+    )]
+    pub repo: Account<'info, Repository>,
+    #[account(
+        mut,
+        close = owner,
+        has_one = repo,
+        seeds = [b"invite", repo.key().as_ref(), invite.secret_hash.as_ref()],
+        bump
+    )]
+    pub invite: Account<'info, Invite>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RecordClone<'info> {
+    #[account(mut, constraint = repo.is_well_formed() @ GitError::CorruptState)]
+    pub repo: Account<'info, Repository>,
+    pub relayer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetNamePolicy<'info> {
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + NamePolicy::INIT_SPACE,
+        seeds = [b"name_policy", owner.key().as_ref()],
+        bump
+    )]
+    pub name_policy: Account<'info, NamePolicy>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetActivityFeed<'info> {
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserActivity::INIT_SPACE,
+        seeds = [b"activity", user.key().as_ref()],
+        bump
+    )]
+    pub activity: Account<'info, UserActivity>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReportRepository<'info> {
+    #[account(mut, constraint = repo.is_well_formed() @ GitError::CorruptState)]
+    pub repo: Account<'info, Repository>,
+    #[account(
+        init,
+        payer = reporter,
+        space = 8 + Report::INIT_SPACE,
+        seeds = [b"report", reporter.key().as_ref(), repo.owner.as_ref(), repo.name.as_bytes()],
+        bump
+    )]
+    pub report: Account<'info, Report>,
+    #[account(mut)]
+    pub reporter: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveReport<'info> {
+    #[account(mut, constraint = repo.is_well_formed() @ GitError::CorruptState)]
+    pub repo: Account<'info, Repository>,
+    #[account(mut, has_one = repo)]
+    pub report: Account<'info, Report>,
+    #[account(seeds = [b"config"], bump, has_one = moderator @ GitError::Unauthorized)]
+    pub config: Account<'info, Config>,
+    pub moderator: Signer<'info>,
+}
+
+/// The layout is not versioned: changing it breaks accounts written by
+/// earlier deployments, which must be recreated (see the README).
+#[account]
+pub struct Repository {
+    pub owner: Pubkey,
+    pub name: String,
+    pub collaborators: Vec<Pubkey>,
+    pub branches: Vec<Branch>,
+    /// Branch names shown first by clients, in pin order. Every entry names
+    /// an existing branch.
+    pub pinned: Vec<String>,
+    /// Unix timestamp of the last mutating instruction on this repository.
+    pub repo_last_activity: i64,
+    /// Sequence number of the last event emitted for this repository.
+    pub event_seq: u64,
+    /// Lamports escrowed at creation and refunded by `close_repo`.
+    pub deposit: u64,
+    /// Hash algorithm of the repository's git objects.
+    pub object_format: ObjectFormat,
+    /// When the first commit was recorded; `None` for never-pushed repos.
+    pub first_commit_at: Option<i64>,
+    /// Digest of the collaborator set and branch tips, refreshed after
+    /// every mutation so indexers can check their reconstructed state.
+    pub state_hash: [u8; 32],
+    /// New location of a moved repository.
+    pub redirect: Option<RepoId>,
+    pub settings: RepoSettings,
+    /// Set by `begin_repo` until `finalize_repo`; pushes are rejected meanwhile.
+    pub pending: bool,
+    /// Owner annotations on collaborators. Only annotated collaborators have
+    /// an entry. Stored on-chain, so readable by anyone.
+    pub collaborator_notes: Vec<CollaboratorNote>,
+    /// Where clients should fetch the repository's objects from.
+    pub storage_config: StorageConfig,
+    /// Key allowed to record verified commits alongside the owner.
+    pub verifier: Option<Pubkey>,
+    /// Mint collaborators must hold to push; `None` disables the gate.
+    pub gate_mint: Option<Pubkey>,
+    /// Smallest `gate_mint` balance, in base units, that allows pushing.
+    pub min_balance: u64,
+    /// Hash of the agreed commit message convention, e.g. of its regex or
+    /// spec document.
+    pub commit_convention: Option<[u8; 32]>,
+    /// Key trusted to attest that commits follow `commit_convention`.
+    pub linter: Option<Pubkey>,
+    /// Copies of this repository under other owners or names, for clients
+    /// to retry fetches against. At most `MAX_MIRRORS`.
+    pub mirrors: Vec<RepoId>,
+    /// Canonical bump of the repository PDA, derived from the creating
+    /// owner's key and the name.
+    pub bump: u8,
+    /// Key allowed to report commit statuses alongside collaborators.
+    pub status_key: Option<Pubkey>,
+    /// Repositories this one depends on, at most `MAX_DEPENDENCIES`.
+    pub dependencies: Vec<Dependency>,
+    /// Per-signer event counts for the current emission window; only
+    /// signers active within the window have an entry.
+    pub emission_counters: Vec<EmissionCounter>,
+    /// Clones reported by `clone_relayer`.
+    pub clone_count: u64,
+    /// Key allowed to call `record_clone`.
+    pub clone_relayer: Option<Pubkey>,
+    /// Unix timestamp of the last `record_clone`.
+    pub last_clone_report: i64,
+    /// Core git settings for working trees. The object format is kept in
+    /// `object_format`, which cannot change after creation.
+    pub git_config: GitConfig,
+    /// Latest unconsumed `prove_owner_control` proof.
+    pub owner_proof: Option<OwnerProof>,
+    /// Seconds without a mutating instruction after which the repository
+    /// turns read-only until the owner calls `touch_repo`.
+    pub auto_archive_after: Option<i64>,
+    /// Merkle root over every branch's name and tip, refreshed with
+    /// `state_hash`, so a single branch can be proven without the rest of
+    /// the account. See `compute_branch_tips_root`.
+    pub branch_tips_root: [u8; 32],
+    /// Keys with the same authority as `owner`, at most `MAX_CO_OWNERS`.
+    /// `owner` stays the key the repository's address derives from and the
+    /// payee of sponsorships.
+    pub co_owners: Vec<Pubkey>,
+    /// Whether a `RepoNameRecord` claims the lowercased `name` for this
+    /// repository. Closing and renaming then need that record.
+    pub name_claimed: bool,
+}
+
+/// Owner-controlled repository flags.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RepoSettings {
+    /// Whether the owner permits this repository to be forked. Advisory
+    /// only: forks are made off-chain, so the program never reads it, and
+    /// clients offering forks are expected to honor it.
+    pub allow_forking: bool,
+    /// Cap on the number of branches, at most `MAX_BRANCHES`.
+    pub max_branches: u16,
+    /// Which events the repository emits; one of the `EVENT_LEVEL_*`
+    /// constants.
+    pub event_level: u8,
+    /// Whether new branch names may contain non-ASCII characters. Such names
+    /// must still be NFC-normalized and free of invisible characters.
+    pub allow_unicode_branch_names: bool,
+    /// Whether `update_branch` may rewrite history.
+    pub push_policy: PushPolicy,
+    /// Seconds a destructive action must wait between request and execution.
+    /// While non-zero, `close_repo` and `rotate_owner_key` are disabled in
+    /// favor of `request_destructive_action`.
+    pub destructive_action_delay: u32,
+    /// Branch update events a single signer may cause per
+    /// `EMISSION_WINDOW_SLOTS`; 0 means unlimited. Updates beyond the
+    /// budget still apply, they just emit nothing.
+    pub emission_budget: u16,
+}
+
+/// Repository-wide rule for branch updates.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PushPolicy {
+    /// Any update is accepted.
+    AllowForce,
+    /// Updates must descend from the current tip.
+    FastForwardOnly,
+}
+
+impl Default for RepoSettings {
+    fn default() -> Self {
+        RepoSettings {
+            allow_forking: true,
+            max_branches: DEFAULT_MAX_BRANCHES,
+            event_level: EVENT_LEVEL_BRANCHES,
+            allow_unicode_branch_names: false,
+            push_policy: PushPolicy::AllowForce,
+            destructive_action_delay: 0,
+            emission_budget: 0,
+        }
+    }
+}
+
+impl RepoSettings {
+    /// Validates and applies the provided fields of `update`.
+    pub fn apply(&mut self, update: RepoSettingsUpdate) -> Result<()> {
+        if let Some(allow_forking) = update.allow_forking {
+            self.allow_forking = allow_forking;
+        }
+        if let Some(max_branches) = update.max_branches {
+            require!(
+                (1..=MAX_BRANCHES).contains(&max_branches),
+                GitError::InvalidBranchLimit
+            );
+            self.max_branches = max_branches;
+        }
+        if let Some(event_level) = update.event_level {
+            require!(event_level <= EVENT_LEVEL_ALL, GitError::InvalidEventLevel);
+            self.event_level = event_level;
+        }
+        if let Some(allow_unicode_branch_names) = update.allow_unicode_branch_names {
+            self.allow_unicode_branch_names = allow_unicode_branch_names;
+        }
+        if let Some(push_policy) = update.push_policy {
+            self.push_policy = push_policy;
+        }
+        if let Some(delay) = update.destructive_action_delay {
+            require!(
+                delay <= MAX_DESTRUCTIVE_ACTION_DELAY,
+                GitError::InvalidTimelock
+            );
+            // Shortening the delay would sidestep it, so that goes through the
+            // timelock as well.
+            require!(
+                delay >= self.destructive_action_delay,
+                GitError::TimelockRequired
+            );
+            self.destructive_action_delay = delay;
+        }
+        if let Some(emission_budget) = update.emission_budget {
+            self.emission_budget = emission_budget;
+        }
+        Ok(())
+    }
+}
+
+/// Partial update of `RepoSettings`; `None` fields are left unchanged.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RepoSettingsUpdate {
+    pub allow_forking: Option<bool>,
+    pub max_branches: Option<u16>,
+    pub event_level: Option<u8>,
+    pub allow_unicode_branch_names: Option<bool>,
+    pub push_policy: Option<PushPolicy>,
+    pub destructive_action_delay: Option<u32>,
+    pub emission_budget: Option<u16>,
+}
+
+impl Repository {
+    /// Records that the repository was just modified and refreshes
+    /// `state_hash`. Every instruction that acts on the repository calls
+    /// this, except moderation, `record_clone`, which counts reads, and
+    /// instructions that close the account. That makes it the one place
+    /// redirects and `auto_archive_after` are enforced: it fails on a
+    /// redirected or archived repository.
+    pub fn touch(&mut self) -> Result<()> {
+        self.ensure_not_redirected()?;
+        let now = Clock::get()?.unix_timestamp;
+        require!(!self.is_archived(now), GitError::RepoAutoArchived);
+        self.repo_last_activity = now;
+        self.state_hash = self.compute_state_hash();
+        self.branch_tips_root = self.compute_branch_tips_root();
+        Ok(())
+    }
+
+    /// SHA-256 over the collaborators sorted by key bytes, followed by each
+    /// branch sorted by name as `name || 0x00 || tip || 0x00`, where `tip`
+    /// is the commit hash, or the target name for symbolic refs.
+    pub fn compute_state_hash(&self) -> [u8; 32] {
+        let mut hasher = Hasher::default();
+        let mut collaborators = self.collaborators.clone();
+        collaborators.sort();
+        for key in collaborators.iter() {
+            hasher.hash(key.as_ref());
+        }
+        let mut branches: Vec<&Branch> = self.branches.iter().collect();
+        branches.sort_by(|a, b| a.name.cmp(&b.name));
+        for branch in branches {
+            let tip = branch.target.as_ref().unwrap_or(&branch.commit.commit_hash);
+            hasher.hashv(&[branch.name.as_bytes(), &[0], tip.as_bytes(), &[0]]);
+        }
+        hasher.result().to_bytes()
+    }
+
+    /// Leaf hashes of `branch_tips_root` paired with their branch names, in
+    /// name order. Each leaf is `SHA-256(0x00 || name || 0x00 || tip)`, with
+    /// `tip` as in `compute_state_hash`.
+    fn branch_leaves(&self) -> Vec<(&str, [u8; 32])> {
+        let mut branches: Vec<&Branch> = self.branches.iter().collect();
+        branches.sort_by(|a, b| a.name.cmp(&b.name));
+        branches
+            .into_iter()
+            .map(|branch| {
+                let tip = branch.target.as_ref().unwrap_or(&branch.commit.commit_hash);
+                let leaf = hashv(&[&[0], branch.name.as_bytes(), &[0], tip.as_bytes()]);
+                (branch.name.as_str(), leaf.to_bytes())
+            })
+            .collect()
+    }
+
+    /// Merkle root over `branch_leaves`; all zeros without branches. Inner
+    /// nodes are `SHA-256(0x01 || left || right)`, and a node without a
+    /// sibling moves up a level unchanged.
+    pub fn compute_branch_tips_root(&self) -> [u8; 32] {
+        let mut level: Vec<[u8; 32]> =
+            self.branch_leaves().into_iter().map(|(_, leaf)| leaf).collect();
+        while level.len() > 1 {
+            level = level.chunks(2).map(merkle_parent).collect();
+        }
+        level.first().copied().unwrap_or_default()
+    }
+
+    /// Leaf index, leaf count and sibling hashes, bottom up, proving
+    /// `branch_name` against `branch_tips_root`. Levels where the node has
+    /// no sibling contribute no hash.
+    pub fn branch_proof(&self, branch_name: &str) -> Option<(u32, u32, Vec<[u8; 32]>)> {
+        let leaves = self.branch_leaves();
+        let leaf_index = leaves.iter().position(|(name, _)| *name == branch_name)?;
+        let mut level: Vec<[u8; 32]> = leaves.iter().map(|(_, leaf)| *leaf).collect();
+        let mut index = leaf_index;
+        let mut proof = Vec::new();
+        while level.len() > 1 {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            level = level.chunks(2).map(merkle_parent).collect();
+            index /= 2;
+        }
+        Some((leaf_index as u32, leaves.len() as u32, proof))
+    }
+
+    /// Whether the collection sizes are within the program's hard limits.
+    /// Accounts that fail this were not produced by this program's own
+    /// instructions and are rejected on entry before any work is done.
+    pub fn is_well_formed(&self) -> bool {
+        self.branches.len() <= MAX_BRANCHES as usize
+            && self.pinned.len() <= MAX_PINNED_BRANCHES
+            && self.mirrors.len() <= MAX_MIRRORS
+            && self.dependencies.len() <= MAX_DEPENDENCIES
+            && self.co_owners.len() <= MAX_CO_OWNERS
+            && self.collaborators.len() <= MAX_COLLABORATORS
+            // At most one note per collaborator.
+            && self.collaborator_notes.len() <= MAX_COLLABORATORS
+            && self.emission_counters.len() <= MAX_EMISSION_COUNTERS
+    }
+
+    /// Hands ownership to `new_owner`, moving the owner's collaborator entry
+    /// with it so the owner stays listed exactly once. A co-owner promoted
+    /// to owner gives up their co-owner slot.
+    pub fn replace_owner(&mut self, new_owner: Pubkey) {
+        let old_owner = self.owner;
+        self.co_owners.retain(|key| *key != new_owner);
+        self.collaborators.retain(|key| *key != new_owner);
+        match self.collaborators.iter().position(|key| *key == old_owner) {
+            Some(index) => self.collaborators[index] = new_owner,
+            None => self.collaborators.push(new_owner),
+        }
+        self.owner = new_owner;
+    }
+
+    /// Whether `key` holds owner authority, as the owner or a co-owner.
+    pub fn is_owner(&self, key: &Pubkey) -> bool {
+        self.owner == *key || self.co_owners.contains(key)
+    }
+
+    /// Fails unless `key` is the repository owner or a co-owner.
+    pub fn ensure_owner(&self, key: &Pubkey) -> Result<()> {
+        require!(self.is_owner(key), GitError::Unauthorized);
+        Ok(())
+    }
+
+    /// Fails unless `key` may write to the repository, i.e. is an owner or
+    /// a collaborator.
+    pub fn ensure_collaborator(&self, key: &Pubkey) -> Result<()> {
+        require!(
+            self.is_owner(key) || self.collaborators.contains(key),
+            GitError::Unauthorized
+        );
+        Ok(())
+    }
+
+    /// Whether the repository has been idle for `auto_archive_after` as of
+    /// `now`.
+    pub fn is_archived(&self, now: i64) -> bool {
+        self.auto_archive_after
+            .is_some_and(|after| now.saturating_sub(self.repo_last_activity) >= after)
+    }
+
+    /// Checks everything that decides whether `key` may move `branch_name`
+    /// with a push at `now`, in the order `update_branch` enforces it.
+    pub fn check_push(
+        &self,
+        branch_name: &str,
+        key: &Pubkey,
+        now: i64,
+    ) -> std::result::Result<(), PushDenial> {
+        if !self.is_owner(key) && !self.collaborators.contains(key) {
+            return Err(PushDenial::NotCollaborator);
+        }
+        if self.redirect.is_some() {
+            return Err(PushDenial::RepoRedirected);
+        }
+        if self.pending {
+            return Err(PushDenial::RepoPending);
+        }
+        if self.is_archived(now) {
+            return Err(PushDenial::RepoArchived);
+        }
+        let branch = self
+            .branches
+            .iter()
+            .find(|b| b.name == branch_name)
+            .ok_or(PushDenial::BranchNotFound)?;
+        if branch.is_symbolic() {
+            return Err(PushDenial::SymbolicRef);
+        }
+        Ok(())
+    }
+
+    /// Fails if a stake gate is set and `key`, unless it is an owner, does
+    /// not hold enough of the gate mint in `token`.
+    pub fn ensure_stake(&self, key: &Pubkey, token: Option<&TokenAccount>) -> Result<()> {
+        let Some(mint) = self.gate_mint else {
+            return Ok(());
+        };
+        if self.is_owner(key) {
+            return Ok(());
+        }
+        let token = token.ok_or(GitError::InvalidStakeAccount)?;
+        require_keys_eq!(token.mint, mint, GitError::InvalidStakeAccount);
+        require_keys_eq!(token.owner, *key, GitError::InvalidStakeAccount);
+        require!(token.amount >= self.min_balance, GitError::InsufficientStake);
+        Ok(())
+    }
+
+    /// Returns the linter's verdict from `proof`, or `false` without one.
+    /// The proof must name the repository's current convention, and
+    /// `linter`, the key that co-signed, must be the configured linter.
+    pub fn check_convention(
+        &self,
+        proof: Option<ConventionProof>,
+        linter: Option<Pubkey>,
+    ) -> Result<bool> {
+        let Some(proof) = proof else {
+            return Ok(false);
+        };
+        require!(
+            self.commit_convention == Some(proof.convention),
+            GitError::ConventionMismatch
+        );
+        require!(
+            self.linter.is_some() && linter == self.linter,
+            GitError::Unauthorized
+        );
+        Ok(proof.passed)
+    }
+
+    /// Fails while a two-phase creation is still appending branches.
+    pub fn ensure_ready(&self) -> Result<()> {
+        require!(!self.pending, GitError::RepoPending);
+        Ok(())
+    }
+
+    /// Fails if destructive actions must go through the timelock.
+    pub fn ensure_no_timelock(&self) -> Result<()> {
+        require!(
+            self.settings.destructive_action_delay == 0,
+            GitError::TimelockRequired
+        );
+        Ok(())
+    }
+
+    /// Takes the owner's control proof, failing unless `signer` made it
+    /// within the last `OWNER_PROOF_MAX_AGE_SLOTS` slots.
+    pub fn consume_owner_proof(&mut self, signer: &Pubkey) -> Result<()> {
+        let proof = self.owner_proof.take().ok_or(GitError::OwnerProofStale)?;
+        require_keys_eq!(proof.prover, *signer, GitError::OwnerProofStale);
+        require!(
+            Clock::get()?.slot.saturating_sub(proof.slot) <= OWNER_PROOF_MAX_AGE_SLOTS,
+            GitError::OwnerProofStale
+        );
+        Ok(())
+    }
+
+    /// Fails unless `record` is given exactly when the repository holds a
+    /// name record. Account constraints check that it is the right one.
+    pub fn ensure_name_record(&self, record: Option<&Account<RepoNameRecord>>) -> Result<()> {
+        require!(
+            record.is_some() == self.name_claimed,
+            GitError::InvalidNameRecord
+        );
+        Ok(())
+    }
+
+    /// Fails if the repository has been redirected elsewhere.
+    pub fn ensure_not_redirected(&self) -> Result<()> {
+        require!(self.redirect.is_none(), GitError::RepoRedirected);
+        Ok(())
+    }
+
+    /// Marks the repository as having history. Only the first call has an
+    /// effect.
+    pub fn record_commit(&mut self) -> Result<()> {
+        if self.first_commit_at.is_none() {
+            self.first_commit_at = Some(Clock::get()?.unix_timestamp);
+        }
+        Ok(())
+    }
+
+    /// Advances and returns the repository's event sequence number. Every
+    /// event carries one so indexers can order events within a slot.
+    pub fn next_event_seq(&mut self) -> u64 {
+        self.event_seq += 1;
+        self.event_seq
+    }
+
+    /// Looks up a branch by name, following symbolic refs to the concrete
+    /// branch they point at. Returns `None` for missing branches and for
+    /// chains deeper than `MAX_SYMBOLIC_REF_DEPTH`.
+    pub fn resolve_branch(&self, name: &str) -> Option<&Branch> {
+        let mut branch = self.branches.iter().find(|b| b.name == name)?;
+        for _ in 0..MAX_SYMBOLIC_REF_DEPTH {
+            match &branch.target {
+                Some(target) => {
+                    branch = self.branches.iter().find(|b| &b.name == target)?;
+                }
+                None => return Some(branch),
+            }
+        }
+        None
+    }
+
+    /// Whether events of the given `EVENT_LEVEL_*` class should be emitted.
+    pub fn emits(&self, level: u8) -> bool {
+        self.settings.event_level >= level
+    }
+
+    /// Emits a `BranchUpdatedEvent` with the branch's current tip, if the
+    /// event level allows branch events and `signer` is within its emission
+    /// budget.
+    pub fn emit_branch_updated(
+        &mut self,
+        repo: Pubkey,
+        branch_name: &str,
+        signer: Pubkey,
+    ) -> Result<()> {
+        if !self.emits(EVENT_LEVEL_BRANCHES) || !self.charge_emission(signer)? {
+            return Ok(());
+        }
+        let commit_hash = self
+            .branches
+            .iter()
+            .find(|b| b.name == branch_name)
+            .map(|b| b.commit.commit_hash.clone())
+            .unwrap_or_default();
+        emit!(BranchUpdatedEvent {
+            repo,
+            seq: self.next_event_seq(),
+            branch: branch_name.to_string(),
+            commit_hash,
+            signer,
+        });
+        Ok(())
+    }
+
+    /// Counts an event against `signer`'s budget for the current window and
+    /// returns whether it may be emitted. Windows start at a signer's first
+    /// event and last `EMISSION_WINDOW_SLOTS`; expired counters are dropped.
+    /// New signers are refused while `MAX_EMISSION_COUNTERS` are tracked.
+    pub fn charge_emission(&mut self, signer: Pubkey) -> Result<bool> {
+        let budget = self.settings.emission_budget;
+        if budget == 0 {
+            return Ok(true);
+        }
+        let slot = Clock::get()?.slot;
+        self.emission_counters
+            .retain(|c| slot < c.window_start.saturating_add(EMISSION_WINDOW_SLOTS));
+        let tracked = self.emission_counters.len();
+        match self.emission_counters.iter_mut().find(|c| c.signer == signer) {
+            Some(counter) if counter.count >= budget => Ok(false),
+            Some(counter) => {
+                counter.count += 1;
+                Ok(true)
+            }
+            None if tracked >= MAX_EMISSION_COUNTERS => Ok(false),
+            None => {
+                self.emission_counters.push(EmissionCounter {
+                    signer,
+                    window_start: slot,
+                    count: 1,
+                });
+                Ok(true)
+            }
+        }
+    }
+
+    /// Name of the default branch, i.e. the concrete branch `HEAD` resolves
+    /// to.
+    pub fn default_branch(&self) -> Option<&str> {
+        self.resolve_branch(HEAD_REF).map(|b| b.name.as_str())
+    }
+
+    /// Adds a concrete branch after checking its name and the branch limit.
+    pub fn add_branch(&mut self, name: String, commit: CommitReference) -> Result<()> {
+        require!(
+            is_valid_branch_name(&name, self.settings.allow_unicode_branch_names),
+            GitError::InvalidBranchName
+        );
+        require!(
+            !self.branches.iter().any(|b| b.name == name),
+            GitError::BranchExists
+        );
+        require!(
+            self.branches.len() < self.settings.max_branches as usize,
+            GitError::TooManyBranches
+        );
+        self.branches.push(Branch {
+            name,
+            commit,
+            ..Default::default()
+        });
+        Ok(())
+    }
+
+    /// Canonical `solar://<owner>/<name>` reference, with the owner in base58.
+    pub fn clone_ref(&self) -> String {
+        format!("{}{}/{}", CLONE_REF_SCHEME, self.owner, self.name)
+    }
+
+    /// Whether `name` is the default branch or the target of a symbolic ref.
+    pub fn is_ref_target(&self, name: &str) -> bool {
+        self.default_branch() == Some(name)
+            || self
+                .branches
+                .iter()
+                .any(|b| b.target.as_deref() == Some(name))
+    }
+
+    /// Removes the named branches along with any pins on them.
+    pub fn remove_branches(&mut self, names: &[String]) {
+        self.branches.retain(|b| !names.contains(&b.name));
+        self.pinned.retain(|name| !names.contains(name));
+    }
+
+    /// Bytes needed to store the repository as it currently stands,
+    /// including the account discriminator.
+    pub fn required_space(&self) -> Result<usize> {
+        Ok(8 + self.try_to_vec()?.len())
+    }
+}
+
+/// Repository names must be non-empty, fit in a PDA seed, and have no
+/// leading or trailing whitespace. Names are rejected rather than trimmed
+/// because the raw name is part of the repository's PDA seeds.
+fn is_valid_repo_name(name: &str) -> bool {
+    // Name records are seeded by the lowercased name, which can be longer
+    // than the name itself and must fit in a seed too.
+    !name.is_empty()
+        && name.len() <= MAX_REPO_NAME_LEN
+        && name.to_lowercase().len() <= MAX_REPO_NAME_LEN
+        && name.trim() == name
+}
+
+/// Checks a new branch name against git's ref naming rules. Names are
+/// ASCII-only unless `allow_unicode` is set, in which case they must be in
+/// NFC form and contain no invisible formatting characters, so that two
+/// names that render identically cannot coexist.
+fn is_valid_branch_name(name: &str, allow_unicode: bool) -> bool {
+    if name.is_empty()
+        || name.len() > MAX_BRANCH_NAME_LEN
+        || name.starts_with('/')
+        || name.starts_with('-')
+        || name.ends_with('/')
+        || name.ends_with('.')
+        || name.ends_with(".lock")
+        || name.contains("..")
+        || name.contains("//")
+        || name.contains("@{")
+    {
+        return false;
+    }
+    if !allow_unicode && !name.is_ascii() {
+        return false;
+    }
+    if !is_nfc(name) {
+        return false;
+    }
+    name.chars().all(|c| {
+        !c.is_control()
+            && !c.is_whitespace()
+            && !matches!(c, '~' | '^' | ':' | '?' | '*' | '[' | '\\')
+            && !matches!(
+                c,
+                '\u{00AD}'
+                    | '\u{200B}'..='\u{200F}'
+                    | '\u{202A}'..='\u{202E}'
+                    | '\u{2060}'..='\u{2064}'
+                    | '\u{FEFF}'
+            )
+    })
+}
+
+/// Raw bytes of a hex commit hash, used as a PDA seed since the hex form of
+/// a hash can exceed the 32-byte seed limit. Invalid hex digits decode as
+/// zero; callers validate the hash separately.
+fn commit_hash_seed(commit_hash: &str) -> Vec<u8> {
+    commit_hash
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            pair.iter().fold(0, |byte, c| {
+                (byte << 4) | (*c as char).to_digit(16).unwrap_or(0) as u8
+            })
+        })
+        .collect()
+}
+
+/// Inner node of `branch_tips_root` over one or two child hashes.
+fn merkle_parent(children: &[[u8; 32]]) -> [u8; 32] {
+    match children {
+        [left, right] => hashv(&[&[1], left, right]).to_bytes(),
+        [only] => *only,
+        _ => unreachable!("chunks(2) yields one or two children"),
+    }
+}
+
+/// Fails unless `statuses` holds a successful `CommitStatus` of this
+/// program for `commit_hash` in `repo` for every context in `required`,
+/// reported by the repository's `status_key`. Statuses collaborators report
+/// themselves do not count, or a pusher could pass their own checks.
+fn ensure_required_checks(
+    program_id: &Pubkey,
+    repo: Pubkey,
+    status_key: Option<Pubkey>,
+    commit_hash: &str,
+    required: &[String],
+    statuses: &[AccountInfo],
+) -> Result<()> {
+    for context in required {
+        let passed = statuses.iter().any(|info| {
+            info.owner == program_id
+                && info
+                    .try_borrow_data()
+                    .ok()
+                    .and_then(|data| CommitStatus::try_deserialize(&mut &data[..]).ok())
+                    .is_some_and(|status| {
+                        status.repo == repo
+                            && Some(status.updated_by) == status_key
+                            && status.commit_hash == commit_hash
+                            && status.context == *context
+                            && status.state == StatusState::Success
+                    })
+        });
+        require!(passed, GitError::RequiredCheckFailed);
+    }
+    Ok(())
+}
+
+/// Fills in a freshly created repository and claims its case-insensitive
+/// name if a record is given or `name_required`. Callers size the account
+/// afterwards.
+#[allow(clippy::too_many_arguments)]
+fn init_repo(
+    repo: &mut Account<Repository>,
+    name_record: Option<&mut Account<RepoNameRecord>>,
+    name_required: bool,
+    owner: Pubkey,
+    name: String,
+    deposit: u64,
+    object_format: Option<ObjectFormat>,
+    git_config: Option<GitConfig>,
+) -> Result<()> {
+    require!(is_valid_repo_name(&name), GitError::InvalidRepoName);
+    repo.name_claimed = claim_name(name_record, repo.key(), name_required)?;
+
+    repo.owner = owner;
+    repo.name = name;
+    // Automatically add the owner as the first collaborator.
+    repo.collaborators.push(owner);
+    repo.settings = RepoSettings::default();
+    repo.object_format = object_format.unwrap_or(ObjectFormat::Sha1);
+    repo.git_config = git_config.unwrap_or_default();
+    repo.deposit = deposit;
+    repo.touch()
+}
+
+/// Points `record` at `repo`, rejecting names that differ from another
+/// repository of the same owner only by case. Returns whether a name was
+/// claimed; fails without a record if `required`.
+fn claim_name(
+    record: Option<&mut Account<RepoNameRecord>>,
+    repo: Pubkey,
+    required: bool,
+) -> Result<bool> {
+    let Some(record) = record else {
+        require!(!required, GitError::InvalidNameRecord);
+        return Ok(false);
+    };
+    require!(
+        record.repo == Pubkey::default() || record.repo == repo,
+        GitError::NameTaken
+    );
+    record.repo = repo;
+    Ok(true)
+}
+
+/// Whether the owner whose `NamePolicy` PDA is `policy` has opted in to
+/// case-insensitive names. A missing policy means no.
+fn case_insensitive_names(program_id: &Pubkey, policy: &AccountInfo) -> bool {
+    policy.owner == program_id
+        && policy
+            .try_borrow_data()
+            .ok()
+            .and_then(|data| NamePolicy::try_deserialize(&mut &data[..]).ok())
+            .is_some_and(|policy| policy.case_insensitive)
+}
+
+/// The repository's code owners mapping at `code_owners`, if it has one.
+fn load_code_owners(program_id: &Pubkey, code_owners: &AccountInfo) -> Option<CodeOwners> {
+    if code_owners.owner != program_id {
+        return None;
+    }
+    let data = code_owners.try_borrow_data().ok()?;
+    CodeOwners::try_deserialize(&mut &data[..]).ok()
+}
+
+/// Fails unless every path in `changed_paths` governed by one of `rules` is
+/// approved by one of that rule's owners signing among `approvers`.
+fn ensure_code_owner_approval(
+    rules: &[CodeOwnerRule],
+    changed_paths: &[String],
+    approvers: &[AccountInfo],
+) -> Result<()> {
+    let approvers: Vec<Pubkey> = approvers
+        .iter()
+        .filter(|account| account.is_signer)
+        .map(|account| *account.key)
+        .collect();
+    for path in changed_paths.iter() {
+        let rule = rules
+            .iter()
+            .filter(|rule| path.starts_with(&rule.path_prefix))
+            .max_by_key(|rule| rule.path_prefix.len());
+        if let Some(rule) = rule {
+            require!(
+                rule.owners.iter().any(|owner| approvers.contains(owner)),
+                GitError::MissingCodeOwnerApproval
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Appends an entry to the actor's activity feed if they passed one in and
+/// have opted in.
+fn record_activity(
+    activity: Option<&mut Account<UserActivity>>,
+    kind: ActivityKind,
+    repo: Pubkey,
+) -> Result<()> {
+    let Some(activity) = activity else {
+        return Ok(());
+    };
+    if !activity.enabled {
+        return Ok(());
+    }
+    if activity.entries.len() == MAX_ACTIVITY_ENTRIES {
+        activity.entries.remove(0);
+    }
+    activity.entries.push(ActivityEntry {
+        kind,
+        repo,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Grows the repository account when its contents no longer fit, charging
+/// the acting signer exactly the additional rent.
+fn grow_repo_to_fit<'info>(
+    repo: &Account<'info, Repository>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let space = repo.required_space()?;
+    if space > repo.to_account_info().data_len() {
+        realloc_repo(
+            repo,
+            &payer.to_account_info(),
+            system_program,
+            space,
+        )?;
+    }
+    Ok(())
+}
+
+/// Reallocs the repository account to `space` bytes and rebalances it to
+/// the rent-exempt minimum plus the escrowed creation deposit. `payer`
+/// covers any shortfall and receives any excess.
+fn realloc_repo<'info>(
+    repo: &Account<'info, Repository>,
+    payer: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+) -> Result<()> {
+    let required = Rent::get()?.minimum_balance(space) + repo.deposit;
+    let repo = repo.to_account_info();
+    let current = repo.lamports();
+    if required > current {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: repo.clone(),
+                },
+            ),
+            required - current,
+        )?;
+    } else if current > required {
+        let refund = current - required;
+        **repo.try_borrow_mut_lamports()? -= refund;
+        **payer.try_borrow_mut_lamports()? += refund;
+    }
+    repo.realloc(space, false)?;
+    Ok(())
+}
+
+/// Identifies a repository by its owner and name.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct RepoId {
+    pub owner: Pubkey,
+    pub name: String,
+}
+
+impl RepoId {
+    pub fn is_valid(&self) -> bool {
+        is_valid_repo_name(&self.name)
+    }
+}
+
+/// Another repository this one builds on, pinned to a commit.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Dependency {
+    pub repo: RepoId,
+    pub pinned_commit: String,
+}
+
+/// Claims a lowercased repository name within an owner's namespace so that
+/// names differing only by case cannot coexist.
+#[account]
+#[derive(InitSpace)]
+pub struct RepoNameRecord {
+    pub repo: Pubkey,
+}
+
+/// An owner's choice of whether their repository names must be unique
+/// regardless of case.
+#[account]
+#[derive(InitSpace)]
+pub struct NamePolicy {
+    pub owner: Pubkey,
+    /// Whether creating or renaming a repository must claim a
+    /// `RepoNameRecord`.
+    pub case_insensitive: bool,
+}
+
+/// Staging buffer for a chunked collaborator import.
+#[account]
+#[derive(InitSpace)]
+pub struct CollaboratorImport {
+    pub repo: Pubkey,
+    #[max_len(MAX_IMPORT_COLLABORATORS)]
+    pub collaborators: Vec<Pubkey>,
+}
+
+/// Where an off-chain relayer should deliver a repository's events.
+#[account]
+#[derive(InitSpace)]
+pub struct NotificationTarget {
+    pub repo: Pubkey,
+    pub endpoint: [u8; 32],
+    pub events: u32,
+}
+
+/// CODEOWNERS-style review policy for a repository.
+#[account]
+#[derive(InitSpace)]
+pub struct CodeOwners {
+    pub repo: Pubkey,
+    #[max_len(MAX_CODE_OWNER_RULES)]
+    pub rules: Vec<CodeOwnerRule>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct CodeOwnerRule {
+    #[max_len(MAX_PATH_PREFIX_LEN)]
+    pub path_prefix: String,
+    #[max_len(MAX_OWNERS_PER_RULE)]
+    pub owners: Vec<Pubkey>,
+}
+
+/// Program-wide settings for a deployment, governed by `admin`.
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,
+    pub moderator: Pubkey,
+    /// Bytes allocated for a new repository before it is resized to fit.
+    pub default_repo_space: u32,
+    /// Minimum deposit, in lamports, escrowed by `create_repo`.
+    pub creation_deposit: u64,
+}
+
+impl Config {
+    pub fn apply(&mut self, update: ConfigUpdate) -> Result<()> {
+        if let Some(moderator) = update.moderator {
+            self.moderator = moderator;
+        }
+        if let Some(default_repo_space) = update.default_repo_space {
+            require!(
+                (REPO_INITIAL_SPACE..=MAX_REPO_INITIAL_SPACE)
+                    .contains(&(default_repo_space as usize)),
+                GitError::InvalidConfig
+            );
+            self.default_repo_space = default_repo_space;
+        }
+        if let Some(creation_deposit) = update.creation_deposit {
+            require!(
+                (MIN_CREATION_DEPOSIT..=MAX_CREATION_DEPOSIT).contains(&creation_deposit),
+                GitError::InvalidConfig
+            );
+            self.creation_deposit = creation_deposit;
+        }
+        Ok(())
+    }
+}
+
+/// Partial update of `Config`; `None` fields are left unchanged.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ConfigUpdate {
+    pub moderator: Option<Pubkey>,
+    pub default_repo_space: Option<u32>,
+    pub creation_deposit: Option<u64>,
+}
+
+/// A user's flag on a repository, awaiting moderator review.
+#[account]
+#[derive(InitSpace)]
+pub struct Report {
+    pub reporter: Pubkey,
+    pub repo: Pubkey,
+    pub reason_code: u8,
+    pub created_at: i64,
+    pub resolved: bool,
+}
+
+/// Latest result of one check on one commit, keyed by repository, commit
+/// and context.
+#[account]
+#[derive(InitSpace)]
+pub struct CommitStatus {
+    pub repo: Pubkey,
+    #[max_len(MAX_COMMIT_HASH_LEN)]
+    pub commit_hash: String,
+    #[max_len(MAX_STATUS_CONTEXT_LEN)]
+    pub context: String,
+    pub state: StatusState,
+    #[max_len(MAX_STATUS_DESCRIPTION_LEN)]
+    pub description: String,
+    pub updated_by: Pubkey,
+    pub updated_at: i64,
+}
+
+/// Outcome of a commit status check.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum StatusState {
+    Pending,
+    Success,
+    Failure,
+}
+
+/// A recurring funding commitment from a sponsor to a repository. The
+/// account's lamports above rent are the escrow `claim_sponsorship` pays
+/// from.
+#[account]
+#[derive(InitSpace)]
+pub struct Sponsorship {
+    pub sponsor: Pubkey,
+    pub repo: Pubkey,
+    /// Lamports paid to the owner per period.
+    pub amount_per_period: u64,
+    pub period_seconds: u32,
+    /// Start of the current period, i.e. when the last payment fell due.
+    pub last_paid: i64,
+}
+
+/// A shareable invitation to become a collaborator on a repository.
+#[account]
+#[derive(InitSpace)]
+pub struct Invite {
+    pub repo: Pubkey,
+    /// SHA-256 of the secret carried by the invite link.
+    pub secret_hash: [u8; 32],
+    /// Unix timestamp from which the invite can no longer be claimed.
+    pub expires_at: Option<i64>,
+}
+
+/// A user's consent for a service to aggregate their public activity.
+#[account]
+#[derive(InitSpace)]
+pub struct ReadGrant {
+    pub user: Pubkey,
+    pub service: Pubkey,
+    /// Bitmask of what the service may read, defined by the relayer.
+    pub scopes: u32,
+    /// Unix timestamp after which the grant no longer applies.
+    pub expires_at: i64,
+}
+
+/// A user's recent actions, oldest first, readable from one account.
+#[account]
+#[derive(InitSpace)]
+pub struct UserActivity {
+    pub user: Pubkey,
+    /// Whether instructions the user signs append to `entries`.
+    pub enabled: bool,
+    #[max_len(MAX_ACTIVITY_ENTRIES)]
+    pub entries: Vec<ActivityEntry>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct ActivityEntry {
+    pub kind: ActivityKind,
+    pub repo: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Kinds of action recorded in a `UserActivity` feed.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum ActivityKind {
+    RepoCreated,
+    BranchPushed,
+}
+
+/// A destructive action waiting out the repository's timelock.
+#[account]
+#[derive(InitSpace)]
+pub struct PendingAction {
+    pub repo: Pubkey,
+    pub action: DestructiveAction,
+    /// Unix timestamp from which `execute_destructive_action` may run.
+    pub execute_after: i64,
+}
+
+/// Operations that are gated by `RepoSettings::destructive_action_delay`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub enum DestructiveAction {
+    CloseRepo,
+    RotateOwnerKey { new_owner: Pubkey },
+    LowerDelay { delay: u32 },
+}
+
+/// Events a signer has caused in their current emission window.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct EmissionCounter {
+    pub signer: Pubkey,
+    /// Slot of the signer's first event in the window.
+    pub window_start: u64,
+    pub count: u16,
+}
+
+/// An owner's free-form annotation on a collaborator.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CollaboratorNote {
+    pub collaborator: Pubkey,
+    pub note: String,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct Branch {
+    pub name: String,
+    pub commit: CommitReference,
+    /// Set for symbolic refs, naming the branch this ref points at. The
+    /// `commit` of a symbolic ref is unused.
+    pub target: Option<String>,
+    /// Commits on this branch missing from the default branch, as last
+    /// recorded by `set_branch_divergence`.
+    pub ahead: u32,
+    /// Commits on the default branch missing from this branch, as last
+    /// recorded by `set_branch_divergence`.
+    pub behind: u32,
+    /// Previous tips, most recent last, at most `MAX_RECENT_TIPS`.
+    pub recent_tips: Vec<String>,
+    /// Last commit that passed verification; empty if none has. Deployment
+    /// tooling should read this rather than the tip.
+    pub last_verified_commit: String,
+    /// Set for throwaway refs created by `push_ephemeral_ref`.
+    pub ephemeral: bool,
+    /// Status contexts that must be `Success` on a commit before it can
+    /// become the tip, at most `MAX_REQUIRED_CHECKS`.
+    pub required_checks: Vec<String>,
+    /// Arweave ID of the branch's built artifacts. Not tied to a commit;
+    /// pushes leave it unchanged.
+    pub artifact_arweave_tx: Option<String>,
+    /// Marks work in progress. Drafts still accept pushes but are left
+    /// out of `list_branches_data` by default.
+    pub draft: bool,
+}
+
+impl Branch {
+    pub fn is_symbolic(&self) -> bool {
+        self.target.is_some()
+    }
+
+    /// Whether the branch has required checks. Protected branches are kept
+    /// by bulk deletes, so their checks cannot be dropped by deleting and
+    /// recreating them.
+    pub fn is_protected(&self) -> bool {
+        !self.required_checks.is_empty()
+    }
+
+    /// Moves the branch to `commit`, remembering the old tip in
+    /// `recent_tips`. Returns the replaced commit.
+    pub fn advance(&mut self, commit: CommitReference) -> CommitReference {
+        let previous = std::mem::replace(&mut self.commit, commit);
+        if !previous.commit_hash.is_empty() {
+            if self.recent_tips.len() == MAX_RECENT_TIPS {
+                self.recent_tips.remove(0);
+            }
+            self.recent_tips.push(previous.commit_hash.clone());
+        }
+        previous
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct CommitReference {
+    pub commit_hash: String,
+    /// Storage identifiers for the commit's objects, in order; a large commit
+    /// may span several. Their format depends on `storage_kind`.
+    pub arweave_txs: Vec<String>,
+    pub storage_kind: StorageKind,
+    /// SHA-256 of the stored payload, so clients can check that the data
+    /// behind `arweave_txs` is what was pushed.
+    pub content_hash: [u8; 32],
+    pub diff_stat: Option<DiffStat>,
+    /// Previous tip of the branch for merge commits; empty for plain pushes.
+    pub parent_hash: String,
+    /// Tip of the merged-in branch for merge commits.
+    pub second_parent: Option<String>,
+    /// Who resolved the conflicts of a merge commit; `None` if the merge
+    /// was clean or the commit is not a merge.
+    pub conflict_resolver: Option<Pubkey>,
+    /// Arweave ID of an off-chain signature over the commit.
+    pub signature_arweave_tx: Option<String>,
+    /// Whether the repository's linter attested that the commit follows the
+    /// commit message convention. Not checked on-chain.
+    pub convention_ok: bool,
+    /// Whether a collaborator attested that the Arweave data is permanently
+    /// stored. False at push time.
+    pub confirmed: bool,
+}
+
+impl CommitReference {
+    /// Builds a plain (non-merge) commit reference after validating the hash
+    /// against `object_format` and each storage ID against `storage_kind`.
+    /// A stored payload must come with a non-zero `content_hash`.
+    pub fn new(
+        object_format: ObjectFormat,
+        commit_hash: String,
+        arweave_txs: Vec<String>,
+        storage_kind: StorageKind,
+        content_hash: [u8; 32],
+        diff_stat: Option<DiffStat>,
+    ) -> Result<Self> {
+        require!(
+            object_format.is_valid_hash(&commit_hash),
+            GitError::InvalidCommitHash
+        );
+        require!(
+            (1..=MAX_BUNDLE_TXS).contains(&arweave_txs.len())
+                && arweave_txs.iter().all(|tx| storage_kind.is_valid_id(tx)),
+            GitError::InvalidStorageId
+        );
+        require!(content_hash != [0; 32], GitError::MissingContentHash);
+        Ok(CommitReference {
+            commit_hash,
+            arweave_txs,
+            storage_kind,
+            content_hash,
+            diff_stat,
+            ..Default::default()
+        })
+    }
+
+    /// Attaches a pointer to a detached commit signature stored on Arweave.
+    pub fn with_signature(mut self, signature_arweave_tx: Option<String>) -> Result<Self> {
+        if let Some(tx) = &signature_arweave_tx {
+            require!(
+                StorageKind::Arweave.is_valid_id(tx),
+                GitError::InvalidStorageId
+            );
+        }
+        self.signature_arweave_tx = signature_arweave_tx;
+        Ok(self)
+    }
+}
+
+/// A linter's verdict on whether a pushed commit follows the repository's
+/// commit message convention.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ConventionProof {
+    /// Hash of the convention the linter checked against.
+    pub convention: [u8; 32],
+    pub passed: bool,
+}
+
+/// Change magnitude of a push, computed by the client.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct DiffStat {
+    pub files_changed: u32,
+    pub insertions: u32,
+    pub deletions: u32,
+}
+
+/// Git object hash algorithm.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectFormat {
+    Sha1,
+    Sha256,
+}
+
+impl ObjectFormat {
+    /// Commit hashes are lowercase hex: 40 characters for SHA-1 and 64 for
+    /// SHA-256.
+    pub fn is_valid_hash(&self, hash: &str) -> bool {
+        let len = match self {
+            ObjectFormat::Sha1 => 40,
+            ObjectFormat::Sha256 => 64,
+        };
+        hash.len() == len
+            && hash
+                .bytes()
+                .all(|c| c.is_ascii_digit() || (b'a'..=b'f').contains(&c))
+    }
+}
+
+/// Evidence that the owner key signed `prove_owner_control` recently.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct OwnerProof {
+    /// Client-chosen challenge the proof answers.
+    pub nonce: [u8; 32],
+    /// Slot the proof was recorded in.
+    pub slot: u64,
+    /// Owner or co-owner key that signed the proof.
+    pub prover: Pubkey,
+}
+
+/// The `core.*` git settings a clone needs to materialize a faithful
+/// working tree.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct GitConfig {
+    /// `core.symlinks`: check symlinks out as links rather than files.
+    pub symlinks: bool,
+    /// `core.ignoreCase`: treat paths differing only by case as the same.
+    pub ignore_case: bool,
+    /// `core.fileMode`: honor the executable bit.
+    pub file_mode: bool,
+    /// `core.autocrlf`.
+    pub autocrlf: AutoCrlf,
+}
+
+impl Default for GitConfig {
+    fn default() -> Self {
+        GitConfig {
+            symlinks: true,
+            ignore_case: false,
+            file_mode: true,
+            autocrlf: AutoCrlf::False,
+        }
+    }
+}
+
+/// Values of `core.autocrlf`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AutoCrlf {
+    False,
+    True,
+    Input,
+}
+
+/// Owner-declared fetch strategy for a repository's objects.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct StorageConfig {
+    pub provider: StorageProvider,
+    /// Preferred gateway or bundler endpoint, an `https://` URL.
+    pub gateway: Option<String>,
+}
+
+impl StorageConfig {
+    pub fn is_valid(&self) -> bool {
+        self.gateway.as_ref().is_none_or(|gateway| {
+            gateway.len() <= MAX_GATEWAY_LEN
+                && gateway.len() > "https://".len()
+                && gateway.starts_with("https://")
+                && gateway.bytes().all(|c| c.is_ascii_graphic())
+        })
+    }
+}
+
+/// Service clients should use to fetch a repository's objects.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StorageProvider {
+    #[default]
+    ArweaveGateway,
+    IpfsGateway,
+    Bundler,
+}
+
+/// Where a commit's objects are stored.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StorageKind {
+    #[default]
+    Arweave,
+    Ipfs,
+}
+
+impl StorageKind {
+    /// Arweave IDs are 43 base64url characters. IPFS IDs must be CIDv1 in
+    /// base32, i.e. a `b` multibase prefix followed by lowercase RFC 4648
+    /// characters.
+    pub fn is_valid_id(&self, id: &str) -> bool {
+        match self {
+            StorageKind::Arweave => {
+                id.len() == 43
+                    && id
+                        .bytes()
+                        .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_')
+            }
+            StorageKind::Ipfs => {
+                id.len() > 1
+                    && id.len() <= 128
+                    && id.starts_with('b')
+                    && id[1..]
+                        .bytes()
+                        .all(|c| c.is_ascii_lowercase() || (b'2'..=b'7').contains(&c))
+            }
+        }
+    }
+}
+
+/// Versioned snapshot of a repository returned by `export_repo`. Fields are
+/// only ever appended, with `version` bumped on each change.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RepoExport {
+    pub version: u8,
+    pub owner: Pubkey,
+    pub name: String,
+    pub collaborators: Vec<Pubkey>,
+    pub branches: Vec<ExportedBranch>,
+    pub pinned: Vec<String>,
+    pub last_activity: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ExportedBranch {
+    pub name: String,
+    pub commit_hash: String,
+    pub storage_ids: Vec<String>,
+    pub storage_kind: StorageKind,
+}
+
+/// A page of branches returned by `list_branches_data`. Fields are only
+/// ever appended.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BranchPage {
+    pub total_pages: u16,
+    pub page: u16,
+    pub branches: Vec<ListedBranch>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ListedBranch {
+    pub name: String,
+    /// Empty for symbolic refs and branches without a commit.
+    pub commit_hash: String,
+    /// Branch a symbolic ref points at.
+    pub target: Option<String>,
+    pub draft: bool,
+}
+
+/// A branch staged by `append_branches`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BranchImport {
+    pub name: String,
+    pub commit_hash: String,
+    pub arweave_txs: Vec<String>,
+    pub storage_kind: StorageKind,
+    pub content_hash: [u8; 32],
+}
+
+/// Confirmation of an applied `update_branch`, returned as return data.
+/// `event_seq` is the repository's sequence number after the update.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PushReceipt {
+    pub repo: Pubkey,
+    pub branch: String,
+    pub new_commit_hash: String,
+    pub slot: u64,
+    pub signer: Pubkey,
+    pub event_seq: u64,
+}
+
+/// Answer of `can_push`, returned as return data.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PushPermission {
+    pub allowed: bool,
+    /// Why the push would be rejected; `None` when `allowed`.
+    pub denial: Option<PushDenial>,
+}
+
+/// Reason a push would be rejected, as reported by `can_push`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PushDenial {
+    /// The key is neither the owner nor a collaborator.
+    NotCollaborator,
+    /// The repository has moved; see its redirect.
+    RepoRedirected,
+    /// The repository is still being imported.
+    RepoPending,
+    BranchNotFound,
+    /// The branch is a symbolic ref and has no commit of its own.
+    SymbolicRef,
+    /// The repository was archived for inactivity; see `touch_repo`.
+    RepoArchived,
+}
+
+/// Result of `verify_collaborators`, returned as return data.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CollaboratorReport {
+    /// Whether every invariant holds.
+    pub well_formed: bool,
+    /// Times the owner appears in the list; should be 1.
+    pub owner_entries: u32,
+    /// Entries other than the owner that repeat an earlier entry; should
+    /// be 0.
+    pub duplicates: u32,
+    /// Length of the list; should be at most `MAX_COLLABORATORS`.
+    pub count: u32,
+}
+
+/// A slice of a serialized `RepoExport`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RepoExportChunk {
+    pub total_chunks: u16,
+    pub chunk: u16,
+    pub data: Vec<u8>,
+}
+
+#[event]
+pub struct BranchUpdatedEvent {
+    pub repo: Pubkey,
+    pub seq: u64,
+    pub branch: String,
+    pub commit_hash: String,
+    pub signer: Pubkey,
+}
+
+#[event]
+pub struct BranchesPrunedEvent {
+    pub repo: Pubkey,
+    pub seq: u64,
+    pub names: Vec<String>,
+}
+
+#[event]
+pub struct BranchesDeletedEvent {
+    pub repo: Pubkey,
+    pub seq: u64,
+    pub prefix: String,
+    pub count: u16,
+    pub names: Vec<String>,
+    pub signer: Pubkey,
+}
+
+#[event]
+pub struct CommitVerifiedEvent {
+    pub repo: Pubkey,
+    pub seq: u64,
+    pub branch: String,
+    pub commit_hash: String,
+    pub verifier: Pubkey,
+}
+
+#[event]
+pub struct BranchSwappedEvent {
+    pub repo: Pubkey,
+    pub seq: u64,
+    pub branch_a: String,
+    pub branch_b: String,
+    pub signer: Pubkey,
+}
+
+#[event]
+pub struct CommitStatusEvent {
+    pub repo: Pubkey,
+    pub seq: u64,
+    pub commit_hash: String,
+    pub context: String,
+    pub state: StatusState,
+    pub signer: Pubkey,
+}
+
+#[event]
+pub struct ReadGrantedEvent {
+    pub user: Pubkey,
+    pub service: Pubkey,
+    pub scopes: u32,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct ReadRevokedEvent {
+    pub user: Pubkey,
+    pub service: Pubkey,
+}
+
+#[event]
+pub struct SponsorshipEvent {
+    pub repo: Pubkey,
+    pub seq: u64,
+    pub sponsor: Pubkey,
+    /// Lamports paid to the owner.
+    pub amount: u64,
+    pub paid_at: i64,
+}
+
+#[event]
+pub struct ArweaveConfirmedEvent {
+    pub repo: Pubkey,
+    pub seq: u64,
+    pub branch: String,
+    pub commit_hash: String,
+    pub signer: Pubkey,
+}
+
+#[event]
+pub struct CloneRecordedEvent {
+    pub repo: Pubkey,
+    pub seq: u64,
+    pub clones: u32,
+    pub clone_count: u64,
+}
+
+#[event]
+pub struct CoOwnerAddedEvent {
+    pub repo: Pubkey,
+    pub seq: u64,
+    pub co_owner: Pubkey,
+    pub signer: Pubkey,
+}
+
+#[event]
+pub struct CoOwnerRemovedEvent {
+    pub repo: Pubkey,
+    pub seq: u64,
+    pub co_owner: Pubkey,
+    pub signer: Pubkey,
+}
+
+#[event]
+pub struct OwnerKeyRotatedEvent {
+    pub repo: Pubkey,
+    pub seq: u64,
+    pub old_owner: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+#[event]
+pub struct NotificationRegisteredEvent {
+    pub repo: Pubkey,
+    pub seq: u64,
+    pub endpoint: [u8; 32],
+    pub events: u32,
+}
+
+#[event]
+pub struct NotificationUnregisteredEvent {
+    pub repo: Pubkey,
+    pub seq: u64,
+}
+
+#[event]
+pub struct ReportEvent {
+    pub repo: Pubkey,
+    pub seq: u64,
+    pub reporter: Pubkey,
+    pub reason_code: u8,
+}
+
+#[event]
+pub struct ReportResolvedEvent {
+    pub repo: Pubkey,
+    pub seq: u64,
+    pub reporter: Pubkey,
+    pub moderator: Pubkey,
+}
+
+#[event]
+pub struct CloneRefEvent {
+    pub repo: Pubkey,
+    pub bump: Option<u8>,
+    pub clone_ref: String,
+    pub default_branch: Option<String>,
+}
+
+#[event]
+pub struct BranchProofEvent {
+    pub repo: Pubkey,
+    pub branch: String,
+    pub root: [u8; 32],
+    pub index: u32,
+    pub leaf_count: u32,
+    pub proof: Vec<[u8; 32]>,
+}
+
+#[event]
+pub struct DestructiveActionRequestedEvent {
+    pub repo: Pubkey,
+    pub seq: u64,
+    pub action: DestructiveAction,
+    pub execute_after: i64,
+}
+
+#[event]
+pub struct BranchResetEvent {
+    pub repo: Pubkey,
+    pub seq: u64,
+    pub branch: String,
+    pub previous_hash: String,
+    pub commit_hash: String,
+    pub reason: String,
+    pub signer: Pubkey,
 }
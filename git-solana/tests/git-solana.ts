@@ -1,16 +1,1901 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
+import { assert } from "chai";
+import { createHash } from "crypto";
 import { GitSolana } from "../target/types/git_solana";
 
+const { PublicKey, SystemProgram } = anchor.web3;
+
 describe("git-solana", () => {
   // Configure the client to use the local cluster.
-  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
 
   const program = anchor.workspace.GitSolana as Program<GitSolana>;
+  const owner = provider.wallet.publicKey;
+
+  const commitHash = (n: number) => n.toString(16).padStart(40, "0");
+  const arweaveTx = (n: number) => n.toString().padStart(43, "A");
+  const contentHash = (n: number) => Array(32).fill(n);
+
+  const repoPda = (repoOwner: anchor.web3.PublicKey, name: string) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("repository"), repoOwner.toBuffer(), Buffer.from(name)],
+      program.programId
+    )[0];
+
+  const nameRecordPda = (repoOwner: anchor.web3.PublicKey, name: string) =>
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("repo_name"),
+        repoOwner.toBuffer(),
+        Buffer.from(name.toLowerCase()),
+      ],
+      program.programId
+    )[0];
+
+  const configPda = PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  )[0];
+
+  before(async () => {
+    // The config is a singleton; it may exist from an earlier run.
+    if (await provider.connection.getAccountInfo(configPda)) return;
+    const programData = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+    )[0];
+    await program.methods
+      .initializeConfig(owner)
+      .accountsPartial({
+        config: configPda,
+        program: program.programId,
+        programData,
+        admin: owner,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  });
+
+  const createRepo = async (name: string) => {
+    const repo = repoPda(owner, name);
+    await program.methods
+      .createRepo(name, new anchor.BN(0), null, null)
+      .accountsPartial({
+        config: configPda,
+        repo,
+        nameRecord: nameRecordPda(owner, name),
+        signer: owner,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    return repo;
+  };
+
+  const createBranch = async (
+    repo: anchor.web3.PublicKey,
+    branch: string,
+    n: number
+  ) =>
+    program.methods
+      .createBranch(
+        branch,
+        commitHash(n),
+        arweaveTx(n),
+        { arweave: {} },
+        contentHash(n)
+      )
+      .accountsPartial({ repo, signer: owner })
+      .rpc();
+
+  const pushBranch = (
+    repo: anchor.web3.PublicKey,
+    branch: string,
+    n: number,
+    ancestors: string[] = []
+  ) =>
+    program.methods
+      .updateBranch(
+        branch,
+        commitHash(n),
+        arweaveTx(n),
+        { arweave: {} },
+        contentHash(n),
+        null,
+        ancestors,
+        null,
+        null
+      )
+      .accountsPartial({ repo, signer: owner })
+      .rpc();
+
+  const tipOf = async (repo: anchor.web3.PublicKey, branch: string) => {
+    const account = await program.account.repository.fetch(repo);
+    return account.branches.find((b) => b.name === branch)?.commit.commitHash;
+  };
+
+  // Applies `settings` over an update that leaves everything unchanged.
+  const updateSettings = (repo: anchor.web3.PublicKey, settings: object) =>
+    program.methods
+      .updateSettings({
+        allowForking: null,
+        maxBranches: null,
+        eventLevel: null,
+        allowUnicodeBranchNames: null,
+        pushPolicy: null,
+        destructiveActionDelay: null,
+        emissionBudget: null,
+        ...settings,
+      })
+      .accountsPartial({ repo, owner })
+      .rpc();
+
+  // A fresh keypair with enough SOL to pay for the accounts it signs for.
+  const fundedKeypair = async () => {
+    const keypair = anchor.web3.Keypair.generate();
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: owner,
+          toPubkey: keypair.publicKey,
+          lamports: anchor.web3.LAMPORTS_PER_SOL,
+        })
+      )
+    );
+    return keypair;
+  };
+
+  const proveOwnerControl = (repo: anchor.web3.PublicKey) =>
+    program.methods
+      .proveOwnerControl(Array(32).fill(0))
+      .accountsPartial({ repo, owner })
+      .instruction();
+
+  it("returns a push receipt matching the applied update", async () => {
+    const repo = await createRepo("receipts");
+    await createBranch(repo, "main", 1);
+
+    const signature = await program.methods
+      .updateBranch(
+        "main",
+        commitHash(2),
+        arweaveTx(2),
+        { arweave: {} },
+        contentHash(2),
+        null,
+        [],
+        null,
+        null
+      )
+      .accountsPartial({ repo, signer: owner })
+      .rpc({ commitment: "confirmed" });
+
+    const tx = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const [data] = tx.meta.returnData.data;
+    const receipt = program.coder.types.decode(
+      "PushReceipt",
+      Buffer.from(data, "base64")
+    );
+
+    const account = await program.account.repository.fetch(repo);
+    const main = account.branches.find((b) => b.name === "main");
+    assert.ok(receipt.repo.equals(repo));
+    assert.equal(receipt.branch, "main");
+    assert.equal(receipt.newCommitHash, main.commit.commitHash);
+    assert.ok(receipt.signer.equals(owner));
+    assert.equal(receipt.slot.toNumber(), tx.slot);
+    assert.equal(receipt.eventSeq.toNumber(), account.eventSeq.toNumber());
+  });
+
+  it("stores the canonical bump of the repository PDA", async () => {
+    const name = "bump-cache";
+    const repo = await createRepo(name);
+
+    const [, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from("repository"), owner.toBuffer(), Buffer.from(name)],
+      program.programId
+    );
+    const account = await program.account.repository.fetch(repo);
+    assert.equal(account.bump, bump);
+  });
+
+  it("rejects creating a repository at a non-canonical PDA", async () => {
+    const name = "non-canonical";
+    const seeds = [Buffer.from("repository"), owner.toBuffer(), Buffer.from(name)];
+    const [, canonicalBump] = PublicKey.findProgramAddressSync(
+      seeds,
+      program.programId
+    );
+    // Walk down from the canonical bump to the next one that is off-curve.
+    let repo: anchor.web3.PublicKey;
+    for (let bump = canonicalBump - 1; !repo && bump >= 0; bump--) {
+      try {
+        repo = PublicKey.createProgramAddressSync(
+          [...seeds, Buffer.from([bump])],
+          program.programId
+        );
+      } catch {
+        // On-curve; try the next bump.
+      }
+    }
+
+    try {
+      await program.methods
+        .createRepo(name, new anchor.BN(0), null, null)
+        .accountsPartial({
+          config: configPda,
+          repo,
+          nameRecord: nameRecordPda(owner, name),
+          signer: owner,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      assert.fail("created a repository at a non-canonical address");
+    } catch (err) {
+      assert.equal(err.error?.errorCode?.code, "ConstraintSeeds");
+    }
+  });
+
+  describe("name policy", () => {
+    const namePolicyPda = PublicKey.findProgramAddressSync(
+      [Buffer.from("name_policy"), owner.toBuffer()],
+      program.programId
+    )[0];
+
+    const setNamePolicy = (caseInsensitive: boolean) =>
+      program.methods
+        .setNamePolicy(caseInsensitive)
+        .accountsPartial({ namePolicy: namePolicyPda, owner })
+        .rpc();
+
+    const rename = (repo: anchor.web3.PublicKey, from: string, to: string) =>
+      program.methods
+        .updateRepo(to, null, null, null, null, null)
+        .accountsPartial({
+          repo,
+          owner,
+          nameRecord: nameRecordPda(owner, from),
+          newNameRecord: nameRecordPda(owner, to),
+          namePolicy: namePolicyPda,
+        })
+        .rpc();
+
+    before(() => setNamePolicy(true));
+    after(() => setNamePolicy(false));
+
+    it("rejects renaming onto a case variant of another repository", async () => {
+      await createRepo("Policy-Alpha");
+      const beta = await createRepo("policy-beta");
+
+      try {
+        await rename(beta, "policy-beta", "POLICY-ALPHA");
+        assert.fail("renamed onto a case variant");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "NameTaken");
+      }
+    });
+
+    it("moves the name record on rename", async () => {
+      const repo = await createRepo("policy-old");
+      await rename(repo, "policy-old", "policy-new");
+
+      const old = nameRecordPda(owner, "policy-old");
+      assert.isNull(await provider.connection.getAccountInfo(old));
+      const record = await program.account.repoNameRecord.fetch(
+        nameRecordPda(owner, "policy-new")
+      );
+      assert.ok(record.repo.equals(repo));
+    });
+
+    it("requires a name record while opted in", async () => {
+      const name = "policy-unclaimed";
+      try {
+        await program.methods
+          .createRepo(name, new anchor.BN(0), null, null)
+          .accountsPartial({
+            config: configPda,
+            repo: repoPda(owner, name),
+            nameRecord: null,
+            namePolicy: namePolicyPda,
+            signer: owner,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        assert.fail("created a repository without claiming its name");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "InvalidNameRecord");
+      }
+    });
+  });
+
+  describe("can_push", () => {
+    const canPush = (
+      repo: anchor.web3.PublicKey,
+      branch: string,
+      key: anchor.web3.PublicKey
+    ) => program.methods.canPush(branch, key).accountsPartial({ repo }).view();
+
+    it("allows a collaborator to push to a concrete branch", async () => {
+      const repo = await createRepo("can-push-ok");
+      await createBranch(repo, "main", 1);
+
+      const permission = await canPush(repo, "main", owner);
+      assert.isTrue(permission.allowed);
+      assert.isNull(permission.denial);
+    });
+
+    it("denies keys that are not collaborators", async () => {
+      const repo = await createRepo("can-push-stranger");
+      await createBranch(repo, "main", 1);
+
+      const stranger = anchor.web3.Keypair.generate().publicKey;
+      const permission = await canPush(repo, "main", stranger);
+      assert.isFalse(permission.allowed);
+      assert.deepEqual(permission.denial, { notCollaborator: {} });
+    });
+
+    it("denies pushes to redirected repositories", async () => {
+      const repo = await createRepo("can-push-moved");
+      await createBranch(repo, "main", 1);
+      await program.methods
+        .setRedirect({ owner, name: "elsewhere" })
+        .accountsPartial({ repo, owner })
+        .rpc();
+
+      const permission = await canPush(repo, "main", owner);
+      assert.isFalse(permission.allowed);
+      assert.deepEqual(permission.denial, { repoRedirected: {} });
+    });
+
+    it("denies pushes while a repository is pending", async () => {
+      const name = "can-push-pending";
+      const repo = repoPda(owner, name);
+      await program.methods
+        .beginRepo(name, 1024, new anchor.BN(0), null, null)
+        .accountsPartial({
+          config: configPda,
+          repo,
+          nameRecord: nameRecordPda(owner, name),
+          signer: owner,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await program.methods
+        .appendBranches([
+          {
+            name: "main",
+            commitHash: commitHash(1),
+            arweaveTxs: [arweaveTx(1)],
+            storageKind: { arweave: {} },
+            contentHash: contentHash(1),
+          },
+        ])
+        .accountsPartial({ repo, owner })
+        .rpc();
+
+      const permission = await canPush(repo, "main", owner);
+      assert.isFalse(permission.allowed);
+      assert.deepEqual(permission.denial, { repoPending: {} });
+    });
+
+    it("denies pushes to missing branches", async () => {
+      const repo = await createRepo("can-push-missing");
+
+      const permission = await canPush(repo, "main", owner);
+      assert.isFalse(permission.allowed);
+      assert.deepEqual(permission.denial, { branchNotFound: {} });
+    });
+
+    it("denies pushes to symbolic refs", async () => {
+      const repo = await createRepo("can-push-symbolic");
+      await createBranch(repo, "main", 1);
+      await program.methods
+        .setSymbolicRef("HEAD", "main")
+        .accountsPartial({ repo, owner })
+        .rpc();
+
+      const permission = await canPush(repo, "HEAD", owner);
+      assert.isFalse(permission.allowed);
+      assert.deepEqual(permission.denial, { symbolicRef: {} });
+    });
+
+    it("denies pushes to repositories archived for inactivity", async () => {
+      const repo = await createRepo("can-push-archived");
+      await createBranch(repo, "main", 1);
+      await program.methods
+        .setAutoArchive(new anchor.BN(1))
+        .accountsPartial({ repo, owner })
+        .rpc();
+
+      await new Promise((resolve) => setTimeout(resolve, 3_000));
+      const permission = await canPush(repo, "main", owner);
+      assert.isFalse(permission.allowed);
+      assert.deepEqual(permission.denial, { repoArchived: {} });
+    });
+  });
+
+  describe("verify_collaborators", () => {
+    const verify = (repo: anchor.web3.PublicKey) =>
+      program.methods.verifyCollaborators().accountsPartial({ repo }).view();
+
+    it("reports a freshly created repository as well-formed", async () => {
+      const repo = await createRepo("verify-clean");
+
+      const report = await verify(repo);
+      assert.isTrue(report.wellFormed);
+      assert.equal(report.ownerEntries, 1);
+      assert.equal(report.duplicates, 0);
+      assert.equal(report.count, 1);
+    });
+
+    // Loaded from tests/fixtures by Anchor.toml; no instruction can
+    // produce these states.
+    it("reports duplicate collaborators", async () => {
+      const repo = new anchor.web3.PublicKey(
+        "4Qi12RVbvmeqvMMKu9duMQs9i99kMZrnEAdzB6P5KFKD"
+      );
+
+      const report = await verify(repo);
+      assert.isFalse(report.wellFormed);
+      assert.equal(report.ownerEntries, 1);
+      assert.equal(report.duplicates, 1);
+      assert.equal(report.count, 3);
+    });
+
+    it("reports an owner missing from the list", async () => {
+      const repo = new anchor.web3.PublicKey(
+        "C3DzR4THAoEDYGE7Qy6ZEV2NkcYWFjEWAPKAKSDHLWy"
+      );
+
+      const report = await verify(repo);
+      assert.isFalse(report.wellFormed);
+      assert.equal(report.ownerEntries, 0);
+    });
+
+    it("drops a co-owner promoted to owner from the co-owners", async () => {
+      const repo = await createRepo("verify-promoted");
+      const coOwner = anchor.web3.Keypair.generate();
+      await program.methods
+        .addCoOwner(coOwner.publicKey)
+        .accountsPartial({ repo, owner })
+        .rpc();
+      await program.methods
+        .rotateOwnerKey()
+        .accountsPartial({ repo, owner, newOwner: coOwner.publicKey })
+        .preInstructions([await proveOwnerControl(repo)])
+        .signers([coOwner])
+        .rpc();
+
+      const account = await program.account.repository.fetch(repo);
+      assert.isTrue(account.owner.equals(coOwner.publicKey));
+      assert.isEmpty(account.coOwners);
+      assert.isTrue((await verify(repo)).wellFormed);
+    });
+
+    it("rejects a repository over the collaborator limit", async () => {
+      const repo = new anchor.web3.PublicKey(
+        "GrGwoBYwF8KvATnmfbzrNWzeUcWvw1Vj753Nygpntits"
+      );
+
+      try {
+        await verify(repo);
+        assert.fail("loaded a repository over the collaborator limit");
+      } catch (err) {
+        const logs: string[] = err.simulationResponse?.logs ?? err.logs ?? [];
+        assert.isTrue(logs.some((line) => line.includes("CorruptState")));
+      }
+    });
+
+    it("rejects a repository over the branch limit", async () => {
+      const repo = new anchor.web3.PublicKey(
+        "GGgAbzg6Xms3xkxVfLUzkCLFUupCzpLSz2zEJHpvUx3W"
+      );
+
+      try {
+        await verify(repo);
+        assert.fail("loaded a repository over the branch limit");
+      } catch (err) {
+        const logs: string[] = err.simulationResponse?.logs ?? err.logs ?? [];
+        assert.isTrue(logs.some((line) => line.includes("CorruptState")));
+      }
+    });
+
+    it("rejects adding a collaborator twice", async () => {
+      const repo = await createRepo("verify-duplicates");
+      const collaborator = anchor.web3.Keypair.generate().publicKey;
+      await program.methods
+        .addCollaborator(collaborator)
+        .accountsPartial({ repo, owner })
+        .rpc();
+
+      try {
+        await program.methods
+          .addCollaborator(collaborator)
+          .accountsPartial({ repo, owner })
+          .rpc();
+        assert.fail("added the same collaborator twice");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "DuplicateCollaborator");
+      }
+      assert.isTrue((await verify(repo)).wellFormed);
+    });
+
+    it("keeps the owner listed after rotating the owner key", async () => {
+      const repo = await createRepo("verify-rotated");
+      const newOwner = anchor.web3.Keypair.generate();
+      await program.methods
+        .rotateOwnerKey()
+        .accountsPartial({ repo, owner, newOwner: newOwner.publicKey })
+        .preInstructions([await proveOwnerControl(repo)])
+        .signers([newOwner])
+        .rpc();
+
+      const report = await verify(repo);
+      assert.isTrue(report.wellFormed);
+      assert.equal(report.ownerEntries, 1);
+      assert.equal(report.count, 1);
+    });
+  });
+
+  describe("set_symbolic_ref", () => {
+    const setRef = (
+      repo: anchor.web3.PublicKey,
+      name: string,
+      target: string
+    ) =>
+      program.methods
+        .setSymbolicRef(name, target)
+        .accountsPartial({ repo, owner })
+        .rpc();
+
+    it("rejects a ref that would close a cycle", async () => {
+      const repo = await createRepo("symref-cycle");
+      await createBranch(repo, "main", 1);
+      await setRef(repo, "a", "main");
+      await setRef(repo, "b", "a");
+
+      try {
+        await setRef(repo, "a", "b");
+        assert.fail("closed a symbolic ref cycle");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "SymbolicRefCycle");
+      }
+    });
+
+    // MAX_SYMBOLIC_REF_DEPTH is 8: `ref8` resolves in eight hops.
+    it("accepts chains up to the maximum depth only", async () => {
+      const repo = await createRepo("symref-depth");
+      await createBranch(repo, "main", 1);
+      await setRef(repo, "ref1", "main");
+      for (let i = 2; i <= 8; i++) {
+        await setRef(repo, `ref${i}`, `ref${i - 1}`);
+      }
+
+      try {
+        await setRef(repo, "ref9", "ref8");
+        assert.fail("chained a symbolic ref past the maximum depth");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "SymbolicRefTooDeep");
+      }
+    });
+  });
+
+  describe("merge_branch", () => {
+    const merge = (repo: anchor.web3.PublicKey, source: string, n: number) =>
+      program.methods
+        .mergeBranch(
+          source,
+          "main",
+          commitHash(n),
+          arweaveTx(n),
+          { arweave: {} },
+          contentHash(n),
+          false,
+          null,
+          ["src/lib.rs"]
+        )
+        .accountsPartial({ repo, signer: owner });
+
+    it("resolves a symbolic source to its branch", async () => {
+      const repo = await createRepo("merge-symref");
+      await createBranch(repo, "main", 1);
+      await createBranch(repo, "feature", 2);
+      await program.methods
+        .setSymbolicRef("current", "feature")
+        .accountsPartial({ repo, owner })
+        .rpc();
+
+      await merge(repo, "current", 3).rpc();
+
+      const account = await program.account.repository.fetch(repo);
+      const main = account.branches.find((b) => b.name === "main");
+      assert.equal(main.commit.commitHash, commitHash(3));
+      assert.equal(main.commit.secondParent, commitHash(2));
+    });
+
+    it("validates the storage ID against the storage kind", async () => {
+      const repo = await createRepo("merge-ipfs");
+      await createBranch(repo, "main", 1);
+      await createBranch(repo, "feature", 2);
+
+      try {
+        await program.methods
+          .mergeBranch(
+            "feature",
+            "main",
+            commitHash(3),
+            arweaveTx(3),
+            { ipfs: {} },
+            contentHash(3),
+            false,
+            null,
+            []
+          )
+          .accountsPartial({ repo, signer: owner })
+          .rpc();
+        assert.fail("recorded an Arweave ID as an IPFS CID");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "InvalidStorageId");
+      }
+    });
+
+    it("accepts a merge its code owners approve", async () => {
+      const repo = await createRepo("merge-owners-ok");
+      await createBranch(repo, "main", 1);
+      await createBranch(repo, "feature", 2);
+      const reviewer = anchor.web3.Keypair.generate();
+      await program.methods
+        .setCodeOwners([{ pathPrefix: "src/", owners: [reviewer.publicKey] }])
+        .accountsPartial({ repo, owner })
+        .rpc();
+
+      await merge(repo, "feature", 3)
+        .remainingAccounts([
+          { pubkey: reviewer.publicKey, isSigner: true, isWritable: false },
+        ])
+        .signers([reviewer])
+        .rpc();
+
+      const account = await program.account.repository.fetch(repo);
+      const main = account.branches.find((b) => b.name === "main");
+      assert.equal(main.commit.commitHash, commitHash(3));
+    });
+
+    it("rejects a merge its code owners have not approved", async () => {
+      const repo = await createRepo("merge-owners-missing");
+      await createBranch(repo, "main", 1);
+      await createBranch(repo, "feature", 2);
+      await program.methods
+        .setCodeOwners([
+          {
+            pathPrefix: "src/",
+            owners: [anchor.web3.Keypair.generate().publicKey],
+          },
+        ])
+        .accountsPartial({ repo, owner })
+        .rpc();
+
+      try {
+        await merge(repo, "feature", 3).rpc();
+        assert.fail("merged without code owner approval");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "MissingCodeOwnerApproval");
+      }
+    });
+
+    it("rejects a symbolic source resolving to the target", async () => {
+      const repo = await createRepo("merge-self");
+      await createBranch(repo, "main", 1);
+      await program.methods
+        .setSymbolicRef("HEAD", "main")
+        .accountsPartial({ repo, owner })
+        .rpc();
+
+      try {
+        await merge(repo, "HEAD", 2).rpc();
+        assert.fail("merged a branch into itself");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "InvalidMerge");
+      }
+    });
+  });
+
+  describe("stake gate", () => {
+    const gatedRepo = async (name: string) => {
+      const repo = await createRepo(name);
+      await createBranch(repo, "main", 1);
+      await createBranch(repo, "feature", 2);
+      const collaborator = anchor.web3.Keypair.generate();
+      await program.methods
+        .addCollaborator(collaborator.publicKey)
+        .accountsPartial({ repo, owner })
+        .rpc();
+      await program.methods
+        .setStakeGate(
+          anchor.web3.Keypair.generate().publicKey,
+          new anchor.BN(1)
+        )
+        .accountsPartial({ repo, owner })
+        .rpc();
+      return { repo, collaborator };
+    };
+
+    const assertUnstaked = async (move: Promise<unknown>) => {
+      try {
+        await move;
+        assert.fail("moved a tip without holding the gate mint");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "InvalidStakeAccount");
+      }
+    };
+
+    it("rejects branch creation by an unstaked collaborator", async () => {
+      const { repo, collaborator } = await gatedRepo("stake-create");
+
+      await assertUnstaked(
+        program.methods
+          .createBranch(
+            "dev",
+            commitHash(3),
+            arweaveTx(3),
+            { arweave: {} },
+            contentHash(3)
+          )
+          .accountsPartial({ repo, signer: collaborator.publicKey })
+          .signers([collaborator])
+          .rpc()
+      );
+    });
+
+    it("rejects a merge by an unstaked collaborator", async () => {
+      const { repo, collaborator } = await gatedRepo("stake-merge");
+
+      await assertUnstaked(
+        program.methods
+          .mergeBranch(
+            "feature",
+            "main",
+            commitHash(3),
+            arweaveTx(3),
+            { arweave: {} },
+            contentHash(3),
+            false,
+            null,
+            []
+          )
+          .accountsPartial({ repo, signer: collaborator.publicKey })
+          .signers([collaborator])
+          .rpc()
+      );
+    });
+  });
+
+  describe("prove_owner_control", () => {
+    const closeRepo = (repo: anchor.web3.PublicKey, name: string) =>
+      program.methods
+        .closeRepo()
+        .accountsPartial({
+          repo,
+          nameRecord: nameRecordPda(owner, name),
+          owner,
+          payee: owner,
+        });
+
+    const assertRejected = async (close: Promise<unknown>) => {
+      try {
+        await close;
+        assert.fail("closed the repository without a fresh proof");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "OwnerProofStale");
+      }
+    };
+
+    it("closes a repository with a fresh proof", async () => {
+      const name = "proof-fresh";
+      const repo = await createRepo(name);
+
+      await closeRepo(repo, name)
+        .preInstructions([await proveOwnerControl(repo)])
+        .rpc();
+      assert.isNull(await provider.connection.getAccountInfo(repo));
+    });
+
+    it("refunds the owner when a co-owner closes", async () => {
+      const name = "proof-co-owner";
+      const repo = await createRepo(name);
+      // Funded: the co-owner's proof grows the repository at their cost.
+      const coOwner = await fundedKeypair();
+      await program.methods
+        .addCoOwner(coOwner.publicKey)
+        .accountsPartial({ repo, owner })
+        .rpc();
+
+      // The owner's proof does not count for the co-owner.
+      await assertRejected(
+        program.methods
+          .closeRepo()
+          .accountsPartial({
+            repo,
+            nameRecord: nameRecordPda(owner, name),
+            owner: coOwner.publicKey,
+            payee: owner,
+          })
+          .preInstructions([await proveOwnerControl(repo)])
+          .signers([coOwner])
+          .rpc()
+      );
+
+      try {
+        await program.methods
+          .closeRepo()
+          .accountsPartial({
+            repo,
+            nameRecord: nameRecordPda(owner, name),
+            owner: coOwner.publicKey,
+            payee: coOwner.publicKey,
+          })
+          .signers([coOwner])
+          .rpc();
+        assert.fail("paid the refund to the co-owner");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "Unauthorized");
+      }
+
+      const prove = await program.methods
+        .proveOwnerControl(Array(32).fill(0))
+        .accountsPartial({ repo, owner: coOwner.publicKey })
+        .instruction();
+      const before = await provider.connection.getBalance(coOwner.publicKey);
+      await program.methods
+        .closeRepo()
+        .accountsPartial({
+          repo,
+          nameRecord: nameRecordPda(owner, name),
+          owner: coOwner.publicKey,
+          payee: owner,
+        })
+        .preInstructions([prove])
+        .signers([coOwner])
+        .rpc();
+      assert.isNull(await provider.connection.getAccountInfo(repo));
+      assert.isAtMost(
+        await provider.connection.getBalance(coOwner.publicKey),
+        before
+      );
+    });
+
+    it("rejects closing without a proof", async () => {
+      const name = "proof-missing";
+      const repo = await createRepo(name);
+
+      await assertRejected(closeRepo(repo, name).rpc());
+    });
+
+    it("rejects a stale proof", async function () {
+      this.timeout(120_000);
+      const name = "proof-stale";
+      const repo = await createRepo(name);
+      const signature = await program.methods
+        .proveOwnerControl(Array(32).fill(1))
+        .accountsPartial({ repo, owner })
+        .rpc({ commitment: "confirmed" });
+      const { slot } = await provider.connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+
+      // OWNER_PROOF_MAX_AGE_SLOTS is 150.
+      while ((await provider.connection.getSlot()) <= slot + 150) {
+        await new Promise((resolve) => setTimeout(resolve, 1_000));
+      }
+      await assertRejected(closeRepo(repo, name).rpc());
+    });
+
+    it("clears the proof once used", async () => {
+      const repo = await createRepo("proof-reuse");
+      const first = anchor.web3.Keypair.generate();
+      await program.methods
+        .rotateOwnerKey()
+        .accountsPartial({ repo, owner, newOwner: first.publicKey })
+        .preInstructions([await proveOwnerControl(repo)])
+        .signers([first])
+        .rpc();
+
+      const account = await program.account.repository.fetch(repo);
+      assert.isNull(account.ownerProof);
+    });
+  });
+
+  describe("prune_empty_branches", () => {
+    it("prunes branches left at the default tip", async () => {
+      const repo = await createRepo("prune-placeholders");
+      await createBranch(repo, "main", 1);
+      await createBranch(repo, "placeholder", 1);
+      await createBranch(repo, "feature", 2);
+      await program.methods
+        .setSymbolicRef("HEAD", "main")
+        .accountsPartial({ repo, owner })
+        .rpc();
+
+      await program.methods
+        .pruneEmptyBranches()
+        .accountsPartial({ repo, signer: owner })
+        .rpc();
+
+      const account = await program.account.repository.fetch(repo);
+      assert.deepEqual(
+        account.branches.map((b) => b.name),
+        ["main", "feature", "HEAD"]
+      );
+    });
+  });
+
+  describe("required checks", () => {
+    let ci: anchor.web3.Keypair;
+
+    before(async () => {
+      ci = await fundedKeypair();
+    });
+
+    const protect = async (repo: anchor.web3.PublicKey, branch: string) => {
+      await program.methods
+        .setStatusKey(ci.publicKey)
+        .accountsPartial({ repo, owner })
+        .rpc();
+      await program.methods
+        .setRequiredChecks(branch, ["ci"])
+        .accountsPartial({ repo, owner })
+        .rpc();
+    };
+
+    const statusPda = (repo: anchor.web3.PublicKey, n: number) =>
+      PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("status"),
+          repo.toBuffer(),
+          Buffer.from(commitHash(n), "hex"),
+          Buffer.from("ci"),
+        ],
+        program.programId
+      )[0];
+
+    const reportSuccess = (
+      repo: anchor.web3.PublicKey,
+      n: number,
+      reporter: anchor.web3.Keypair
+    ) =>
+      program.methods
+        .setCommitStatus(commitHash(n), "ci", { success: {} }, "")
+        .accountsPartial({
+          repo,
+          status: statusPda(repo, n),
+          signer: reporter.publicKey,
+        })
+        .signers([reporter])
+        .rpc();
+
+    const push = (
+      repo: anchor.web3.PublicKey,
+      n: number,
+      signer: anchor.web3.Keypair
+    ) =>
+      program.methods
+        .updateBranch(
+          "main",
+          commitHash(n),
+          arweaveTx(n),
+          { arweave: {} },
+          contentHash(n),
+          null,
+          [],
+          null,
+          null
+        )
+        .accountsPartial({ repo, signer: signer.publicKey })
+        .remainingAccounts([
+          { pubkey: statusPda(repo, n), isSigner: false, isWritable: false },
+        ])
+        .signers([signer])
+        .rpc();
+
+    it("needs a status key", async () => {
+      const repo = await createRepo("checks-no-key");
+      await createBranch(repo, "main", 1);
+
+      try {
+        await program.methods
+          .setRequiredChecks("main", ["ci"])
+          .accountsPartial({ repo, owner })
+          .rpc();
+        assert.fail("required checks nobody may report");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "StatusKeyNotSet");
+      }
+    });
+
+    it("accepts a success reported by the status key", async () => {
+      const repo = await createRepo("checks-ci");
+      await createBranch(repo, "main", 1);
+      await protect(repo, "main");
+      const collaborator = await fundedKeypair();
+      await program.methods
+        .addCollaborator(collaborator.publicKey)
+        .accountsPartial({ repo, owner })
+        .rpc();
+
+      await reportSuccess(repo, 2, ci);
+      await push(repo, 2, collaborator);
+
+      const account = await program.account.repository.fetch(repo);
+      const main = account.branches.find((b) => b.name === "main");
+      assert.equal(main.commit.commitHash, commitHash(2));
+    });
+
+    it("rejects a success the pusher reported", async () => {
+      const repo = await createRepo("checks-self");
+      await createBranch(repo, "main", 1);
+      await protect(repo, "main");
+      const collaborator = await fundedKeypair();
+      await program.methods
+        .addCollaborator(collaborator.publicKey)
+        .accountsPartial({ repo, owner })
+        .rpc();
+
+      await reportSuccess(repo, 2, collaborator);
+      try {
+        await push(repo, 2, collaborator);
+        assert.fail("passed a check with a self-reported status");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "RequiredCheckFailed");
+      }
+    });
+
+    it("applies to resets", async () => {
+      const repo = await createRepo("checks-reset");
+      await createBranch(repo, "main", 1);
+      await protect(repo, "main");
+
+      try {
+        await program.methods
+          .resetBranch(
+            "main",
+            commitHash(2),
+            arweaveTx(2),
+            { arweave: {} },
+            contentHash(2),
+            "roll back",
+            true
+          )
+          .accountsPartial({ repo, signer: owner })
+          .rpc();
+        assert.fail("reset a protected branch without a passing check");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "RequiredCheckFailed");
+      }
+    });
+
+    it("keeps protected branches out of bulk deletes", async () => {
+      const repo = await createRepo("checks-delete");
+      await createBranch(repo, "main", 1);
+      await createBranch(repo, "release/1", 2);
+      await createBranch(repo, "release/2", 3);
+      await protect(repo, "release/1");
+
+      await program.methods
+        .deleteBranchesByPrefix("release/")
+        .accountsPartial({ repo, signer: owner })
+        .rpc();
+
+      const account = await program.account.repository.fetch(repo);
+      const release = account.branches.find((b) => b.name === "release/1");
+      assert.deepEqual(release.requiredChecks, ["ci"]);
+      assert.isUndefined(account.branches.find((b) => b.name === "release/2"));
+    });
+
+    it("keeps protected branches when pruning", async () => {
+      const repo = await createRepo("checks-prune");
+      await createBranch(repo, "main", 1);
+      await createBranch(repo, "release", 1);
+      await program.methods
+        .setSymbolicRef("HEAD", "main")
+        .accountsPartial({ repo, owner })
+        .rpc();
+      await protect(repo, "release");
+
+      await program.methods
+        .pruneEmptyBranches()
+        .accountsPartial({ repo, signer: owner })
+        .rpc();
+
+      const account = await program.account.repository.fetch(repo);
+      assert.isDefined(account.branches.find((b) => b.name === "release"));
+    });
+  });
+
+  describe("invite links", () => {
+    it("admits one collaborator and closes on the claim", async () => {
+      const repo = await createRepo("invite-single");
+      const secret = Array(32).fill(7);
+      const secretHash = [
+        ...createHash("sha256").update(Buffer.from(secret)).digest(),
+      ];
+      const invite = PublicKey.findProgramAddressSync(
+        [Buffer.from("invite"), repo.toBuffer(), Buffer.from(secretHash)],
+        program.programId
+      )[0];
+      await program.methods
+        .createInviteLink(secretHash, null)
+        .accountsPartial({ repo, invite, owner })
+        .rpc();
+
+      const claim = async (claimer: anchor.web3.Keypair) =>
+        program.methods
+          .claimInviteLink(secret)
+          .accountsPartial({
+            repo,
+            invite,
+            owner,
+            claimer: claimer.publicKey,
+          })
+          .signers([claimer])
+          .rpc();
+
+      const first = await fundedKeypair();
+      await claim(first);
+      assert.isNull(await provider.connection.getAccountInfo(invite));
+      const account = await program.account.repository.fetch(repo);
+      assert.isTrue(
+        account.collaborators.some((k) => k.equals(first.publicKey))
+      );
+
+      // The secret is public now, but the invite is gone.
+      try {
+        await claim(await fundedKeypair());
+        assert.fail("claimed a used invite");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "AccountNotInitialized");
+      }
+    });
+  });
+
+  describe("sponsorship", () => {
+    const AMOUNT = 1_000_000;
+
+    const sponsor = async (repo: anchor.web3.PublicKey) => {
+      const sponsorKey = await fundedKeypair();
+      await program.methods
+        .sponsorRepository(new anchor.BN(AMOUNT), 1, 3)
+        .accountsPartial({ repo, owner, sponsor: sponsorKey.publicKey })
+        .signers([sponsorKey])
+        .rpc();
+      const sponsorship = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("sponsorship"),
+          sponsorKey.publicKey.toBuffer(),
+          repo.toBuffer(),
+        ],
+        program.programId
+      )[0];
+      return { sponsorKey, sponsorship };
+    };
+
+    it("does not count as repository activity", async () => {
+      const repo = await createRepo("sponsor-activity");
+      const before = await program.account.repository.fetch(repo);
+
+      await new Promise((resolve) => setTimeout(resolve, 2_000));
+      await sponsor(repo);
+
+      const after = await program.account.repository.fetch(repo);
+      assert.equal(
+        after.repoLastActivity.toString(),
+        before.repoLastActivity.toString()
+      );
+    });
+
+    it("pays elapsed periods to the owner when it ends", async () => {
+      const repo = await createRepo("sponsor-end");
+      const { sponsorKey, sponsorship } = await sponsor(repo);
+      const held = (await provider.connection.getAccountInfo(sponsorship))
+        .lamports;
+
+      await new Promise((resolve) => setTimeout(resolve, 3_000));
+      const before = await provider.connection.getBalance(sponsorKey.publicKey);
+      await program.methods
+        .endSponsorship()
+        .accountsPartial({
+          sponsorship,
+          repo,
+          owner,
+          sponsor: sponsorKey.publicKey,
+        })
+        .signers([sponsorKey])
+        .rpc();
+
+      const refund =
+        (await provider.connection.getBalance(sponsorKey.publicKey)) - before;
+      assert.isNull(await provider.connection.getAccountInfo(sponsorship));
+      assert.isAtMost(refund, held - AMOUNT);
+    });
+  });
+
+  describe("push policy", () => {
+    const fastForwardOnly = async (name: string) => {
+      const repo = await createRepo(name);
+      await createBranch(repo, "main", 1);
+      await updateSettings(repo, { pushPolicy: { fastForwardOnly: {} } });
+      return repo;
+    };
+
+    it("accepts a push descending from the tip", async () => {
+      const repo = await fastForwardOnly("ff-only-descends");
+
+      await pushBranch(repo, "main", 2, [commitHash(1)]);
+      assert.equal(await tipOf(repo, "main"), commitHash(2));
+    });
+
+    it("rejects a push that does not descend from the tip", async () => {
+      const repo = await fastForwardOnly("ff-only-rewrite");
+
+      try {
+        await pushBranch(repo, "main", 2, [commitHash(3)]);
+        assert.fail("rewrote history under a fast-forward-only policy");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "NonFastForward");
+      }
+    });
+
+    it("accepts any push under the default policy", async () => {
+      const repo = await createRepo("allow-force");
+      await createBranch(repo, "main", 1);
+
+      await pushBranch(repo, "main", 2);
+      assert.equal(await tipOf(repo, "main"), commitHash(2));
+    });
+  });
+
+  describe("reset_branch", () => {
+    const reset = (
+      repo: anchor.web3.PublicKey,
+      n: number,
+      reason: string,
+      force: boolean,
+      signer?: anchor.web3.Keypair
+    ) =>
+      program.methods
+        .resetBranch(
+          "main",
+          commitHash(n),
+          arweaveTx(n),
+          { arweave: {} },
+          contentHash(n),
+          reason,
+          force
+        )
+        .accountsPartial({ repo, signer: signer?.publicKey ?? owner })
+        .signers(signer ? [signer] : [])
+        .rpc();
+
+    it("rolls a branch back and remembers the old tip", async () => {
+      const repo = await createRepo("reset-back");
+      await createBranch(repo, "main", 1);
+      await pushBranch(repo, "main", 2);
+
+      await reset(repo, 1, "bad release", false);
+
+      const account = await program.account.repository.fetch(repo);
+      const main = account.branches.find((b) => b.name === "main");
+      assert.equal(main.commit.commitHash, commitHash(1));
+      assert.include(main.recentTips, commitHash(2));
+    });
+
+    it("rejects a reset without a reason", async () => {
+      const repo = await createRepo("reset-no-reason");
+      await createBranch(repo, "main", 1);
+
+      try {
+        await reset(repo, 2, "", false);
+        assert.fail("reset a branch without a reason");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "InvalidResetReason");
+      }
+    });
+
+    it("needs force under a fast-forward-only policy", async () => {
+      const repo = await createRepo("reset-ff-only");
+      await createBranch(repo, "main", 1);
+      await updateSettings(repo, { pushPolicy: { fastForwardOnly: {} } });
+
+      try {
+        await reset(repo, 2, "rewrite", false);
+        assert.fail("reset a fast-forward-only branch without force");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "NonFastForward");
+      }
+      await reset(repo, 2, "rewrite", true);
+      assert.equal(await tipOf(repo, "main"), commitHash(2));
+    });
+
+    it("leaves forced resets to the owner", async () => {
+      const repo = await createRepo("reset-ff-collab");
+      await createBranch(repo, "main", 1);
+      await updateSettings(repo, { pushPolicy: { fastForwardOnly: {} } });
+      const collaborator = await fundedKeypair();
+      await program.methods
+        .addCollaborator(collaborator.publicKey)
+        .accountsPartial({ repo, owner })
+        .rpc();
+
+      try {
+        await reset(repo, 2, "rewrite", true, collaborator);
+        assert.fail("a collaborator force-reset a fast-forward-only branch");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "Unauthorized");
+      }
+    });
+  });
+
+  describe("delete_branches_by_prefix", () => {
+    const deleteByPrefix = (repo: anchor.web3.PublicKey, prefix: string) =>
+      program.methods
+        .deleteBranchesByPrefix(prefix)
+        .accountsPartial({ repo, signer: owner })
+        .rpc();
+
+    const branchNames = async (repo: anchor.web3.PublicKey) =>
+      (await program.account.repository.fetch(repo)).branches.map(
+        (b) => b.name
+      );
+
+    it("deletes the branches under the prefix", async () => {
+      const repo = await createRepo("prefix-delete");
+      await createBranch(repo, "main", 1);
+      await createBranch(repo, "feature/a", 2);
+      await createBranch(repo, "feature/b", 3);
+
+      await deleteByPrefix(repo, "feature/");
+      assert.deepEqual(await branchNames(repo), ["main"]);
+    });
+
+    it("keeps the default branch", async () => {
+      const repo = await createRepo("prefix-default");
+      await createBranch(repo, "main", 1);
+      await createBranch(repo, "maint", 2);
+      await program.methods
+        .setSymbolicRef("HEAD", "main")
+        .accountsPartial({ repo, owner })
+        .rpc();
+
+      await deleteByPrefix(repo, "main");
+      assert.deepEqual(await branchNames(repo), ["main", "HEAD"]);
+    });
+
+    it("rejects an empty prefix", async () => {
+      const repo = await createRepo("prefix-empty");
+      await createBranch(repo, "main", 1);
+
+      try {
+        await deleteByPrefix(repo, "");
+        assert.fail("deleted branches by an empty prefix");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "EmptyBranchPrefix");
+      }
+    });
+  });
+
+  describe("swap_branches", () => {
+    const releaseRepo = async (name: string) => {
+      const repo = await createRepo(name);
+      await createBranch(repo, "live", 1);
+      await createBranch(repo, "staging", 2);
+      return repo;
+    };
+
+    const swap = (
+      repo: anchor.web3.PublicKey,
+      force: boolean,
+      signer?: anchor.web3.Keypair
+    ) =>
+      program.methods
+        .swapBranches("live", "staging", force)
+        .accountsPartial({ repo, signer: signer?.publicKey ?? owner })
+        .signers(signer ? [signer] : [])
+        .rpc();
+
+    it("exchanges the tips of two branches", async () => {
+      const repo = await releaseRepo("swap-tips");
+
+      await swap(repo, false);
+      assert.equal(await tipOf(repo, "live"), commitHash(2));
+      assert.equal(await tipOf(repo, "staging"), commitHash(1));
+    });
+
+    it("rejects a missing branch", async () => {
+      const repo = await createRepo("swap-missing");
+      await createBranch(repo, "live", 1);
+
+      try {
+        await swap(repo, false);
+        assert.fail("swapped with a missing branch");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "BranchNotFound");
+      }
+    });
+
+    it("needs force under a fast-forward-only policy", async () => {
+      const repo = await releaseRepo("swap-ff-only");
+      await updateSettings(repo, { pushPolicy: { fastForwardOnly: {} } });
+
+      try {
+        await swap(repo, false);
+        assert.fail("swapped fast-forward-only branches without force");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "NonFastForward");
+      }
+      await swap(repo, true);
+      assert.equal(await tipOf(repo, "live"), commitHash(2));
+    });
+
+    it("rejects a forced swap by a collaborator", async () => {
+      const repo = await releaseRepo("swap-ff-collab");
+      await updateSettings(repo, { pushPolicy: { fastForwardOnly: {} } });
+      const collaborator = await fundedKeypair();
+      await program.methods
+        .addCollaborator(collaborator.publicKey)
+        .accountsPartial({ repo, owner })
+        .rpc();
+
+      try {
+        await swap(repo, true, collaborator);
+        assert.fail("a collaborator force-swapped fast-forward-only branches");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "Unauthorized");
+      }
+    });
+  });
+
+  describe("set_commit_status", () => {
+    const statusPda = (repo: anchor.web3.PublicKey, context: string) =>
+      PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("status"),
+          repo.toBuffer(),
+          Buffer.from(commitHash(1), "hex"),
+          Buffer.from(context),
+        ],
+        program.programId
+      )[0];
+
+    const setStatus = (
+      repo: anchor.web3.PublicKey,
+      context: string,
+      signer: anchor.web3.Keypair
+    ) =>
+      program.methods
+        .setCommitStatus(commitHash(1), context, { success: {} }, "passed")
+        .accountsPartial({
+          repo,
+          status: statusPda(repo, context),
+          signer: signer.publicKey,
+        })
+        .signers([signer])
+        .rpc();
+
+    it("keeps a status per context from collaborators", async () => {
+      const repo = await createRepo("status-contexts");
+      const collaborator = await fundedKeypair();
+      await program.methods
+        .addCollaborator(collaborator.publicKey)
+        .accountsPartial({ repo, owner })
+        .rpc();
+
+      await setStatus(repo, "build", collaborator);
+      await setStatus(repo, "test", collaborator);
+
+      for (const context of ["build", "test"]) {
+        const status = await program.account.commitStatus.fetch(
+          statusPda(repo, context)
+        );
+        assert.equal(status.context, context);
+        assert.deepEqual(status.state, { success: {} });
+        assert.isTrue(status.updatedBy.equals(collaborator.publicKey));
+      }
+    });
+
+    it("accepts the status key", async () => {
+      const repo = await createRepo("status-key");
+      const ci = await fundedKeypair();
+      await program.methods
+        .setStatusKey(ci.publicKey)
+        .accountsPartial({ repo, owner })
+        .rpc();
+
+      await setStatus(repo, "ci", ci);
+      const status = await program.account.commitStatus.fetch(
+        statusPda(repo, "ci")
+      );
+      assert.isTrue(status.updatedBy.equals(ci.publicKey));
+    });
+
+    it("rejects other keys", async () => {
+      const repo = await createRepo("status-stranger");
+      const stranger = await fundedKeypair();
+
+      try {
+        await setStatus(repo, "ci", stranger);
+        assert.fail("a stranger reported a commit status");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "Unauthorized");
+      }
+    });
+  });
+
+  describe("record_clone", () => {
+    const relayedRepo = async (name: string) => {
+      const repo = await createRepo(name);
+      const relayer = anchor.web3.Keypair.generate();
+      await program.methods
+        .setCloneRelayer(relayer.publicKey)
+        .accountsPartial({ repo, owner })
+        .rpc();
+      return { repo, relayer };
+    };
+
+    const recordClone = (
+      repo: anchor.web3.PublicKey,
+      relayer: anchor.web3.Keypair,
+      clones: number
+    ) =>
+      program.methods
+        .recordClone(clones)
+        .accountsPartial({ repo, relayer: relayer.publicKey })
+        .signers([relayer])
+        .rpc();
+
+    it("counts clones the relayer reports", async () => {
+      const { repo, relayer } = await relayedRepo("clones-counted");
+
+      await recordClone(repo, relayer, 5);
+      const account = await program.account.repository.fetch(repo);
+      assert.equal(account.cloneCount.toNumber(), 5);
+    });
+
+    it("rejects reports from other keys", async () => {
+      const { repo } = await relayedRepo("clones-stranger");
+
+      try {
+        await recordClone(repo, anchor.web3.Keypair.generate(), 5);
+        assert.fail("counted clones reported by a stranger");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "Unauthorized");
+      }
+    });
+
+    it("rejects reports over the per-report cap", async () => {
+      const { repo, relayer } = await relayedRepo("clones-cap");
+
+      try {
+        // MAX_CLONES_PER_REPORT is 1000.
+        await recordClone(repo, relayer, 1_001);
+        assert.fail("counted an oversized report");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "InvalidCloneReport");
+      }
+    });
+
+    it("rejects a second report within the interval", async () => {
+      const { repo, relayer } = await relayedRepo("clones-too-soon");
+      await recordClone(repo, relayer, 5);
+
+      try {
+        await recordClone(repo, relayer, 6);
+        assert.fail("counted two reports within the interval");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "CloneReportTooSoon");
+      }
+    });
+  });
+
+  describe("auto-archive", () => {
+    const setAutoArchive = (
+      repo: anchor.web3.PublicKey,
+      after: anchor.BN | null
+    ) =>
+      program.methods
+        .setAutoArchive(after)
+        .accountsPartial({ repo, owner })
+        .rpc();
+
+    const archivedRepo = async (name: string) => {
+      const repo = await createRepo(name);
+      await createBranch(repo, "main", 1);
+      await setAutoArchive(repo, new anchor.BN(1));
+      await new Promise((resolve) => setTimeout(resolve, 3_000));
+      return repo;
+    };
+
+    it("rejects pushes once the threshold lapses", async () => {
+      const repo = await archivedRepo("archive-idle");
+
+      try {
+        await pushBranch(repo, "main", 2);
+        assert.fail("pushed to an archived repository");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "RepoAutoArchived");
+      }
+    });
+
+    it("accepts pushes after touch_repo", async () => {
+      const repo = await archivedRepo("archive-touched");
+
+      // In one transaction, so the one-second threshold cannot lapse again.
+      await program.methods
+        .updateBranch(
+          "main",
+          commitHash(2),
+          arweaveTx(2),
+          { arweave: {} },
+          contentHash(2),
+          null,
+          [],
+          null,
+          null
+        )
+        .accountsPartial({ repo, signer: owner })
+        .preInstructions([
+          await program.methods
+            .touchRepo()
+            .accountsPartial({ repo, owner })
+            .instruction(),
+        ])
+        .rpc();
+      assert.equal(await tipOf(repo, "main"), commitHash(2));
+    });
+
+    it("rejects a threshold that is not positive", async () => {
+      const repo = await createRepo("archive-zero");
+
+      try {
+        await setAutoArchive(repo, new anchor.BN(0));
+        assert.fail("set a zero auto-archive threshold");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "InvalidThreshold");
+      }
+    });
+  });
+
+  describe("branch tips root", () => {
+    const sha256 = (...parts: Buffer[]) =>
+      createHash("sha256").update(Buffer.concat(parts)).digest();
+    const leaf = (name: string, tip: string) =>
+      sha256(
+        Buffer.from([0]),
+        Buffer.from(name),
+        Buffer.from([0]),
+        Buffer.from(tip)
+      );
+    const parent = (left: Buffer, right: Buffer) =>
+      sha256(Buffer.from([1]), left, right);
+
+    // Mirrors `compute_branch_tips_root` over (name, tip) pairs.
+    const tipsRoot = (tips: [string, string][]) => {
+      let level = [...tips]
+        .sort(([a], [b]) => (a < b ? -1 : a > b ? 1 : 0))
+        .map(([name, tip]) => leaf(name, tip));
+      while (level.length > 1) {
+        const next: Buffer[] = [];
+        for (let i = 0; i < level.length; i += 2) {
+          next.push(
+            i + 1 < level.length ? parent(level[i], level[i + 1]) : level[i]
+          );
+        }
+        level = next;
+      }
+      return level[0] ?? Buffer.alloc(32);
+    };
+
+    const storedRoot = async (repo: anchor.web3.PublicKey) =>
+      Buffer.from(
+        (await program.account.repository.fetch(repo)).branchTipsRoot
+      );
+
+    const threeBranches = async (name: string) => {
+      const repo = await createRepo(name);
+      await createBranch(repo, "main", 1);
+      await createBranch(repo, "feature", 2);
+      await program.methods
+        .setSymbolicRef("HEAD", "main")
+        .accountsPartial({ repo, owner })
+        .rpc();
+      return repo;
+    };
+
+    it("commits to every branch tip as they move", async () => {
+      const repo = await threeBranches("tips-root");
+      assert.isTrue(
+        (await storedRoot(repo)).equals(
+          tipsRoot([
+            ["main", commitHash(1)],
+            ["feature", commitHash(2)],
+            ["HEAD", "main"],
+          ])
+        )
+      );
+
+      await pushBranch(repo, "feature", 3);
+      assert.isTrue(
+        (await storedRoot(repo)).equals(
+          tipsRoot([
+            ["main", commitHash(1)],
+            ["feature", commitHash(3)],
+            ["HEAD", "main"],
+          ])
+        )
+      );
+    });
+
+    it("proves a branch against the root", async () => {
+      const repo = await threeBranches("tips-proof");
+
+      const { events } = await program.methods
+        .getBranchProof("main")
+        .accountsPartial({ repo })
+        .simulate();
+      const { index, leafCount, proof } = events[0].data as any;
+
+      let node = leaf("main", commitHash(1));
+      let position = index;
+      let width = leafCount;
+      const siblings = [...proof];
+      while (width > 1) {
+        if ((position ^ 1) < width) {
+          const sibling = Buffer.from(siblings.shift());
+          node = position % 2 ? parent(sibling, node) : parent(node, sibling);
+        }
+        position = Math.floor(position / 2);
+        width = Math.ceil(width / 2);
+      }
+      assert.isTrue(node.equals(await storedRoot(repo)));
+    });
+
+    it("rejects a proof for a missing branch", async () => {
+      const repo = await threeBranches("tips-missing");
+
+      try {
+        await program.methods
+          .getBranchProof("nope")
+          .accountsPartial({ repo })
+          .rpc();
+        assert.fail("proved a missing branch");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "BranchNotFound");
+      }
+    });
+  });
+
+  describe("redirects", () => {
+    const redirect = (repo: anchor.web3.PublicKey) =>
+      program.methods
+        .setRedirect({ owner, name: "elsewhere" })
+        .accountsPartial({ repo, owner })
+        .rpc();
+
+    it("rejects pushes while redirected", async () => {
+      const repo = await createRepo("redirect-push");
+      await createBranch(repo, "main", 1);
+      await redirect(repo);
+
+      try {
+        await pushBranch(repo, "main", 2);
+        assert.fail("pushed to a redirected repository");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "RepoRedirected");
+      }
+    });
+
+    it("accepts pushes again once the redirect is cleared", async () => {
+      const repo = await createRepo("redirect-clear");
+      await createBranch(repo, "main", 1);
+      await redirect(repo);
+      await program.methods
+        .clearRedirect()
+        .accountsPartial({ repo, owner })
+        .rpc();
+
+      await pushBranch(repo, "main", 2);
+      assert.equal(await tipOf(repo, "main"), commitHash(2));
+    });
+
+    it("rejects a redirect to the repository itself", async () => {
+      const name = "redirect-self";
+      const repo = await createRepo(name);
+
+      try {
+        await program.methods
+          .setRedirect({ owner, name })
+          .accountsPartial({ repo, owner })
+          .rpc();
+        assert.fail("redirected a repository to itself");
+      } catch (err) {
+        assert.equal(err.error?.errorCode?.code, "InvalidRedirect");
+      }
+    });
+  });
+
+  describe("compute budget", () => {
+    // Documented ceiling for the hot instructions at the largest size below.
+    // Raise it deliberately, never to make a regression pass.
+    const HOT_PATH_CU_BUDGET = 180_000;
+    const SIZES = [1, 50, 200];
+
+    const unitsConsumed = async (signature: string) => {
+      const tx = await provider.connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      return tx.meta.computeUnitsConsumed;
+    };
+
+    for (const size of SIZES) {
+      it(`update_branch stays within budget with ${size} branches`, async () => {
+        const repo = await createRepo(`cu-branches-${size}`);
+        await program.methods
+          .updateSettings({
+            allowForking: null,
+            maxBranches: 256,
+            eventLevel: null,
+            allowUnicodeBranchNames: null,
+            pushPolicy: null,
+            destructiveActionDelay: null,
+            emissionBudget: null,
+          })
+          .accountsPartial({ repo, owner })
+          .rpc();
+        for (let i = 0; i < size; i++) {
+          await createBranch(repo, `b${i}`, i + 1);
+        }
+
+        const signature = await program.methods
+          .updateBranch(
+            "b0",
+            commitHash(size + 1),
+            arweaveTx(size + 1),
+            { arweave: {} },
+            contentHash(1),
+            null,
+            [],
+            null,
+            null
+          )
+          .accountsPartial({ repo, signer: owner })
+          .rpc({ commitment: "confirmed" });
+
+        const units = await unitsConsumed(signature);
+        assert.isAtMost(units, HOT_PATH_CU_BUDGET);
+      });
+
+      it(`add_collaborator stays within budget with ${size} collaborators`, async () => {
+        const repo = await createRepo(`cu-collabs-${size}`);
+        // The owner is already the first collaborator.
+        for (let i = 1; i < size; i++) {
+          await program.methods
+            .addCollaborator(anchor.web3.Keypair.generate().publicKey)
+            .accountsPartial({ repo, owner })
+            .rpc();
+        }
+
+        const signature = await program.methods
+          .addCollaborator(anchor.web3.Keypair.generate().publicKey)
+          .accountsPartial({ repo, owner })
+          .rpc({ commitment: "confirmed" });
 
-  it("Is initialized!", async () => {
-    // Add your test here.
-    const tx = await program.methods.initialize().rpc();
-    console.log("Your transaction signature", tx);
+        const units = await unitsConsumed(signature);
+        assert.isAtMost(units, HOT_PATH_CU_BUDGET);
+      });
+    }
   });
 });
@@ -3,6 +3,7 @@ const GitRepository = require("./GitRepository");
 const fs = require("fs");
 const path = require("path");
 const { execSync } = require("child_process");
+const crypto = require("crypto");
 const SolanaClient = require("../utils/solanaClient");
 const { uploadGitBundle, downloadGitBundle } = require("../utils/arweave/git-arweave");
 
@@ -210,6 +211,7 @@ class SolanaGitRepository extends GitRepository {
           const bundlePath = path.join(this.bundlesDir, `${owner}-${repo}-${Date.now()}.bundle`);
           execSync(`git -C "${repoDir}" bundle create "${bundlePath}" --all`, { stdio: 'ignore' });
           console.log(`Created bundle at ${bundlePath}`);
+          const contentHash = crypto.createHash("sha256").update(fs.readFileSync(bundlePath)).digest();
           
           // Upload to Arweave
           let arweaveTxId = null;
@@ -242,7 +244,8 @@ class SolanaGitRepository extends GitRepository {
                   repo,
                   cmd.ref,
                   cmd.destId,
-                  arweaveTxId
+                  arweaveTxId,
+                  contentHash
                 );
                 console.log(`Updated Solana state for ${cmd.ref}`);
               } catch (solanaError) {
@@ -10,6 +10,7 @@ const { execSync } = require('child_process');
 const path = require('path');
 const fs = require('fs');
 const os = require('os');
+const crypto = require('crypto');
 const { PublicKey } = require('@solana/web3.js');
 
 // Import Solana and Arweave integrations
@@ -469,12 +470,14 @@ app.post('/:owner/:repo/git-receive-pack', (req, res) => {
               
               // Create a bundle for Arweave if enabled
               let arweaveTxId = null;
+              let contentHash = null;
               if (arweaveEnabled) {
                 try {
                   // Create a bundle of the repository for Arweave
                   const bundlePath = path.join(BUNDLES_DIR, `${owner}-${repo}-${Date.now()}.bundle`);
                   execSync(`git -C "${repoPath}" bundle create "${bundlePath}" --all`, { stdio: 'inherit' });
                   console.log(`Created bundle at ${bundlePath}`);
+                  contentHash = crypto.createHash('sha256').update(fs.readFileSync(bundlePath)).digest();
                   
                   // Upload to Arweave
                   const keyPath = solanaClient.getWalletKeyPath();
@@ -524,13 +527,20 @@ app.post('/:owner/:repo/git-receive-pack', (req, res) => {
                         // We use the Git URL owner for display, but server wallet is the real owner
                     console.log(`Git URL owner: ${owner} (for display only)`);
                     
+                    // Ancestors let fast-forward-only repositories accept the push
+                    const ancestors = execSync(
+                      `git -C "${repoPath}" rev-list --skip=1 --max-count=32 ${commitHash}`
+                    ).toString().split('\n').filter(Boolean);
+                    
                     // The server's wallet is the actual Solana owner
                     const updateResult = await solanaClient.updateBranch(
                       owner,
                       repo,
                       refName,
                       commitHash,
-                      arweaveTxId
+                      arweaveTxId,
+                      contentHash,
+                      ancestors
                     );
                     
                     console.log(`Successfully updated branch ${refName} in Solana`);
@@ -568,7 +578,7 @@ app.post('/:owner/:repo/git-receive-pack', (req, res) => {
 app.get('/:owner/:repo/unsigned-tx', async (req, res) => {
   try {
     const { owner, repo } = req.params;
-    const { branch, commit } = req.query;
+    const { branch, commit, arweaveTx, contentHash } = req.query;
     
     if (!solanaEnabled || !solanaClient) {
       return res.status(503).json({ 
@@ -576,15 +586,15 @@ app.get('/:owner/:repo/unsigned-tx', async (req, res) => {
       });
     }
     
-    if (!branch || !commit) {
+    if (!branch || !commit || !arweaveTx || !contentHash) {
       return res.status(400).json({ 
-        error: 'Branch and commit parameters are required' 
+        error: 'Branch, commit, arweaveTx and contentHash parameters are required' 
       });
     }
     
     // Create unsigned transaction
     const unsignedTx = await solanaClient.createUnsignedTransaction(
-      owner, repo, branch, commit
+      owner, repo, branch, commit, arweaveTx, contentHash
     );
     
     res.json(unsignedTx);
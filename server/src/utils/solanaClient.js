@@ -35,10 +35,28 @@ if (!idl) {
 // Program ID from the contract (declared in lib.rs)
 const PROGRAM_ID = new PublicKey('5TQo5Bf6yXp9uywEFbp9YKUyveD2pe2LVXRjY2aWRup5');
 
+// Storage kind recorded for every branch tip the server writes
+const ARWEAVE_STORAGE = { arweave: {} };
+
+// Longest ancestor list update_branch accepts (MAX_LINEAGE_LEN in lib.rs)
+const MAX_LINEAGE_LEN = 32;
+
 // Localnet connection
 // Default localnet URL, change if your validator is running on a different port
 const LOCALNET_URL = 'https://api.devnet.solana.com';
 
+// Converts a SHA-256 content hash, given as a Buffer or hex string, into
+// the byte array the program expects
+function toContentHash(contentHash) {
+  const bytes = typeof contentHash === 'string'
+    ? Buffer.from(contentHash, 'hex')
+    : Buffer.from(contentHash || []);
+  if (bytes.length !== 32) {
+    throw new Error('A 32-byte SHA-256 content hash of the stored bundle is required');
+  }
+  return Array.from(bytes);
+}
+
 // Function to load a keypair for signing transactions
 // Fallback to generating a random keypair if file doesn't exist
 function loadOrCreateKeypair(keypairPath) {
@@ -138,6 +156,88 @@ class SolanaClient {
     return pda;
   }
 
+  /**
+   * Find the PDA of the program config, which holds the creation deposit
+   */
+  findConfigPDA() {
+    const [pda, _] = PublicKey.findProgramAddressSync(
+      [Buffer.from('config')],
+      PROGRAM_ID
+    );
+
+    return pda;
+  }
+
+  /**
+   * Find the PDA of the record claiming a repository name for an owner.
+   * Names are claimed case-insensitively, so the seed is lowercased.
+   */
+  findNameRecordPDA(owner, repoName) {
+    const [pda, _] = PublicKey.findProgramAddressSync(
+      [Buffer.from('repo_name'), owner.toBuffer(), Buffer.from(repoName.toLowerCase())],
+      PROGRAM_ID
+    );
+
+    return pda;
+  }
+
+  /**
+   * Find the PDA of an owner's name policy
+   */
+  findNamePolicyPDA(owner) {
+    const [pda, _] = PublicKey.findProgramAddressSync(
+      [Buffer.from('name_policy'), owner.toBuffer()],
+      PROGRAM_ID
+    );
+
+    return pda;
+  }
+
+  /**
+   * Build the instruction that moves a branch to a new commit, creating the
+   * branch first if the repository does not have it yet
+   */
+  async buildBranchInstruction(signer, repoPDA, branchName, commitHash, arweaveTx, contentHash, ancestors = []) {
+    if (!arweaveTx) {
+      throw new Error('An Arweave transaction ID is required to update a branch');
+    }
+    const hash = toContentHash(contentHash);
+    const repoAccount = await this.program.account.repository.fetch(repoPDA);
+    const exists = repoAccount.branches.some(branch => branch.name === branchName);
+
+    const accounts = {
+      repo: repoPDA,
+      signer,
+      systemProgram: SystemProgram.programId,
+      gateToken: null,
+      linter: null,
+      activity: null
+    };
+
+    // Branches are no longer created implicitly by updateBranch
+    if (!exists) {
+      return this.program.methods
+        .createBranch(branchName, commitHash, arweaveTx, ARWEAVE_STORAGE, hash)
+        .accounts(accounts)
+        .instruction();
+    }
+
+    return this.program.methods
+      .updateBranch(
+        branchName,
+        commitHash,
+        arweaveTx,
+        ARWEAVE_STORAGE,
+        hash,
+        null,
+        ancestors.slice(0, MAX_LINEAGE_LEN),
+        null,
+        null
+      )
+      .accounts(accounts)
+      .instruction();
+  }
+
   /**
    * Get repository data from Solana
    */
@@ -165,7 +265,7 @@ class SolanaClient {
         branches: repoAccount.branches.map(branch => ({
           name: branch.name,
           commitHash: branch.commit.commitHash,
-          arweaveTx: branch.commit.arweaveTx
+          arweaveTx: branch.commit.arweaveTxs[0]
         }))
       };
     } catch (error) {
@@ -187,12 +287,7 @@ class SolanaClient {
    */
   async createRepository(ownerStr, repoName) {
     if (!this.program || !this.validatorRunning) {
-      console.log('Solana integration not available - skipping repository creation');
-      return {
-        name: repoName,
-        owner: ownerStr || (this.wallet ? this.wallet.publicKey.toString() : 'unknown'),
-        branches: []
-      };
+      throw new Error('Solana integration not available');
     }
     
     try {
@@ -223,7 +318,7 @@ class SolanaClient {
             branches: existingRepo.branches.map(branch => ({
               name: branch.name,
               commitHash: branch.commit.commitHash,
-              arweaveTx: branch.commit.arweaveTx
+              arweaveTx: branch.commit.arweaveTxs[0]
             }))
           };
         }
@@ -235,13 +330,18 @@ class SolanaClient {
         console.log(`Repository ${repoName} does not exist, creating it now...`);
       }
       
-      // Send the createRepo instruction
+      // Send the createRepo instruction. A zero deposit is raised to the
+      // minimum set in the program config.
       const tx = await this.program.methods
-        .createRepo(repoName)
+        .createRepo(repoName, new BN(0), null, null)
         .accounts({
+          config: this.findConfigPDA(),
           repo: repoPDA,
+          nameRecord: this.findNameRecordPDA(serverWallet, repoName),
+          namePolicy: this.findNamePolicyPDA(serverWallet),
           signer: serverWallet,
-          systemProgram: SystemProgram.programId
+          systemProgram: SystemProgram.programId,
+          activity: null
         })
         .signers([this.wallet])
         .rpc();
@@ -253,12 +353,7 @@ class SolanaClient {
       return await this.getRepository(serverWallet.toString(), repoName);
     } catch (error) {
       console.error('Error creating repository:', error.message);
-      // Return a mock repository object
-      return {
-        name: repoName,
-        owner: this.wallet.publicKey.toString(),
-        branches: []
-      };
+      throw error;
     }
   }
 
@@ -269,19 +364,12 @@ class SolanaClient {
    * @param {string} branchName - The name of the branch to update
    * @param {string} commitHash - The new commit hash
    * @param {string} arweaveTx - The Arweave transaction ID
+   * @param {Buffer|string} contentHash - SHA-256 of the uploaded bundle
+   * @param {string[]} ancestors - Ancestor hashes of the commit, needed under a fast-forward-only push policy
    */
-  async updateBranch(repoOwner, repoName, branchName, commitHash, arweaveTx) {
+  async updateBranch(repoOwner, repoName, branchName, commitHash, arweaveTx, contentHash, ancestors = []) {
     if (!this.program || !this.validatorRunning) {
-      console.log(`Solana integration not available - skipping branch update for ${branchName}`);
-      return {
-        name: repoName,
-        owner: repoOwner,
-        branches: [{
-          name: branchName,
-          commitHash: commitHash,
-          arweaveTx: arweaveTx
-        }]
-      };
+      throw new Error('Solana integration not available');
     }
     
     console.log(`Updating branch with Git owner=${repoOwner} (display only), repo=${repoName}, branch=${branchName}`);
@@ -301,15 +389,17 @@ class SolanaClient {
       console.log(`Actual Solana owner: ${serverWallet.toString()} (server wallet)`);
       console.log(`Repository PDA: ${repoPDA.toString()}`);
       
-      // Send the updateBranch instruction
-      const tx = await this.program.methods
-        .updateBranch(branchName, commitHash, arweaveTx)
-        .accounts({
-          repo: repoPDA,
-          signer: serverWallet
-        })
-        .signers([this.wallet])
-        .rpc();
+      // Send the createBranch or updateBranch instruction
+      const ix = await this.buildBranchInstruction(
+        serverWallet,
+        repoPDA,
+        branchName,
+        commitHash,
+        arweaveTx,
+        contentHash,
+        ancestors
+      );
+      const tx = await this.provider.sendAndConfirm(new web3.Transaction().add(ix), [this.wallet]);
       
       console.log(`Updated branch ${branchName} in repository ${repoName} with transaction ${tx}`);
       
@@ -318,23 +408,14 @@ class SolanaClient {
       return await this.getRepository(serverWallet.toString(), repoName);
     } catch (error) {
       console.error('Error updating branch:', error.message);
-      // Return a mock repository object
-      return {
-        name: repoName,
-        owner: this.wallet.publicKey.toString(),
-        branches: [{
-          name: branchName,
-          commitHash: commitHash,
-          arweaveTx: arweaveTx
-        }]
-      };
+      throw error;
     }
   }
 
   /**
    * Create an unsigned transaction for client-side signing
    */
-  async createUnsignedTransaction(owner, repoName, branchName, commitHash, arweaveTx = null) {
+  async createUnsignedTransaction(owner, repoName, branchName, commitHash, arweaveTx, contentHash, ancestors = []) {
     if (!this.program) {
       throw new Error('Solana program not initialized');
     }
@@ -348,14 +429,17 @@ class SolanaClient {
     // Get recent blockhash for transaction
     const { blockhash, lastValidBlockHeight } = await this.connection.getLatestBlockhash();
     
-    // Create the instruction but don't sign it
-    const ix = await this.program.methods
-      .updateBranch(branchName, commitHash, arweaveTx || 'pending')
-      .accounts({
-        repo: repoPDA,
-        signer: ownerPubkey // Use the owner's pubkey, not the server's
-      })
-      .instruction();
+    // Create the instruction but don't sign it. The owner's pubkey signs,
+    // not the server's.
+    const ix = await this.buildBranchInstruction(
+      ownerPubkey,
+      repoPDA,
+      branchName,
+      commitHash,
+      arweaveTx,
+      contentHash,
+      ancestors
+    );
     
     // Create transaction message
     const messageV0 = new TransactionMessage({
@@ -460,7 +544,7 @@ class SolanaClient {
             branches: repoData.branches.map(branch => ({
               name: branch.name,
               commitHash: branch.commit.commitHash,
-              arweaveTx: branch.commit.arweaveTx
+              arweaveTx: branch.commit.arweaveTxs[0]
             }))
           });
         } catch (err) {